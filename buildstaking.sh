@@ -34,6 +34,12 @@ FILE_CODE_ID="code.txt"
 ADDR_WORKSHOP="juno1htjut8n7jv736dhuqnad5mcydk6tf4ydeaan4s"
 ADDR_ARBITER="juno1htjut8n7jv736dhuqnad5mcydk6tf4ydeaan4s"
 ADDR_ADMIN=$ADDR_WORKSHOP
+ADDR_ARTIST=$ADDR_WORKSHOP
+WORK_ID="testwork"
+RECEIVE_WORK_ID="testreceive"
+COMMISSION_ID="testcommission"
+WORK_URL="https://example.com/testwork"
+WORK_URL_HASH=$(echo -n "$WORK_URL" | sha256sum | cut -d' ' -f1)
 
 ###################################################################################################
 ###################################################################################################
@@ -139,7 +145,7 @@ PrintIsAdmin() {
 #Print Special Escrow Details
 PrintDetailsQuery() {
     CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
-    junod query wasm contract-state smart $CONTRACT_ADDR '{"details":{"id":"'$ADDR_WORKSHOP'"}}' $NODECHAIN
+    junod query wasm contract-state smart $CONTRACT_ADDR '{"details":{"id":"'$WORK_ID'"}}' $NODECHAIN
 }
 
 #Print Constants
@@ -148,6 +154,11 @@ PrintConstants() {
     junod query wasm contract-state smart $CONTRACT_ADDR '{"constants":{}}' $NODECHAIN
 }
 
+PrintPauseState() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    junod query wasm contract-state smart $CONTRACT_ADDR '{"pause_state":{}}' $NODECHAIN
+}
+
 ###################################################################################################
 ###################################################################################################
 ###################################################################################################
@@ -155,28 +166,54 @@ PrintConstants() {
 #Create Test Escrow
 CreateEscrow() {
     CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
-    junod tx wasm execute $CONTRACT_ADDR '{"create":{"id":"'$ADDR_ACHILLES'", "arbiter":"'$ADDR_WORKSHOP'", "recipient":"'$ADDR_ACHILLES'"}}' $WALLET $TXFLAG
+    junod tx wasm execute $CONTRACT_ADDR '{"create":{"id":"'$WORK_ID'", "client":"'$ADDR_WORKSHOP'", "arbiter":"'$ADDR_ARBITER'", "work_title":"Test work", "work_desc":"", "work_url_hash":"'$WORK_URL_HASH'", "image_url":"", "account_min_stake_amount":"1", "stake_amount":"100"}}' $WALLET $TXFLAG --amount 1000$DENOM
+}
+
+#Publish WORK_URL once the test work is funded
+RevealWorkUrl() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    junod tx wasm execute $CONTRACT_ADDR '{"reveal_work_url":{"id":"'$WORK_ID'", "work_url":"'$WORK_URL'"}}' $WALLET $TXFLAG
 }
 
-#Transfer to Created Test Escrow
+#Commission paid by the workshop wallet to ADDR_ARTIST
+CreateCommission() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    junod tx wasm execute $CONTRACT_ADDR '{"create_commission":{"id":"'$COMMISSION_ID'", "artist":"'$ADDR_ARTIST'", "arbiter":"'$ADDR_ARBITER'", "work_title":"Test commission", "work_desc":"", "submit_deadline":604800, "review_period":259200}}' $WALLET $TXFLAG --amount 1000$DENOM
+}
+
+PrintCommission() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    junod query wasm contract-state smart $CONTRACT_ADDR '{"commission":{"id":"'$COMMISSION_ID'", "addr":"'$ADDR_WORKSHOP'"}}' $NODECHAIN
+}
+
+#Stake crew in the Created Test Escrow
 TopUp() {
     CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
-    junod tx wasm execute $CONTRACT_ADDR '{"top_up":{"id":"'$ADDR_ACHILLES'"}}' $WALLET $TXFLAG
+    MSG=$(echo -n '{"top_up":{"id":"'$WORK_ID'", "start_time":0, "end_time":0}}' | base64 -w 0)
+    junod tx wasm execute $CONTRACT_CREW '{"send":{"contract":"'$CONTRACT_ADDR'", "amount":"15", "msg":"'$MSG'"}}' $WALLET $TXFLAG
 }
 
+#Create a second test escrow staked in crew, the sent tokens are the client bond
 CreateReceive() {
     CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
-    junod tx wasm execute $CONTRACT_ADDR '{"receive":{"sender":"'$ADDR_ACHILLES'", "amount":"15", "msg": { "id":"'$ADDR_ACHILLES'", "arbiter":"'$ADDR_ARBITER'", "recipient":"'$ADDR_ACHILLES'" }}}' $WALLET $TXFLAG
+    MSG=$(echo -n '{"create":{"id":"'$RECEIVE_WORK_ID'", "client":"'$ADDR_WORKSHOP'", "arbiter":"'$ADDR_ARBITER'", "work_title":"Test work", "work_desc":"", "work_url_hash":"'$WORK_URL_HASH'", "image_url":"", "account_min_stake_amount":"1", "stake_amount":"100"}}' | base64 -w 0)
+    junod tx wasm execute $CONTRACT_CREW '{"send":{"contract":"'$CONTRACT_ADDR'", "amount":"15", "msg":"'$MSG'"}}' $WALLET $TXFLAG
 }
 
 Approve() {
     CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
-    junod tx wasm execute $CONTRACT_ADDR '{"approve":{"id":"'$ADDR_ACHILLES'"}}' $WALLET $TXFLAG
+    junod tx wasm execute $CONTRACT_ADDR '{"approve":{"id":"'$WORK_ID'"}}' $WALLET $TXFLAG
 }
 
 Refund() {
     CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
-    junod tx wasm execute $CONTRACT_ADDR '{"refund":{"id":"'$ADDR_ACHILLES'"}}' $WALLET $TXFLAG
+    junod tx wasm execute $CONTRACT_ADDR '{"refund":{"id":"'$WORK_ID'"}}' $WALLET $TXFLAG
+}
+
+#Withdraw everything credited by approvals and failed refunds
+Claim() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    junod tx wasm execute $CONTRACT_ADDR '{"claim":{}}' $WALLET $TXFLAG
 }
 
 
@@ -185,6 +222,19 @@ SetConstant() {
     junod tx wasm execute $CONTRACT_ADDR '{"set_constant":{"manager_addr":"'$ADDR_WORKSHOP'", "min_stake":"1", "rate_client":"10", "rate_manager":"0"}}' $WALLET $TXFLAG
 }
 
+#Pause every deposit path, set PAUSE_SCOPE to "create" "top_up" or "receive" to narrow it
+Pause() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    SCOPE=${PAUSE_SCOPE:-all}
+    junod tx wasm execute $CONTRACT_ADDR '{"set_pause":{"scope":"'$SCOPE'", "paused":true}}' $WALLET $TXFLAG
+}
+
+Unpause() {
+    CONTRACT_ADDR=$(cat $FILE_CONTRACT_ADDR)
+    SCOPE=${PAUSE_SCOPE:-all}
+    junod tx wasm execute $CONTRACT_ADDR '{"set_pause":{"scope":"'$SCOPE'", "paused":false}}' $WALLET $TXFLAG
+}
+
 
 #################################### End of Function ###################################################
 if [[ $PARAM == "" ]]; then
@@ -1,29 +1,50 @@
-use std::ops::Div;
-
+use std::convert::TryFrom;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, SubMsg, WasmMsg, Uint128, Timestamp
+    from_binary, to_binary, Addr, Api, BankMsg, Binary, ContractResult, Deps, DepsMut, Env,
+    MessageInfo, Order, Reply, Coin, Decimal, Response, StdError, StdResult, Storage, SubMsg, WasmMsg, Uint128,
+    Uint256, Timestamp
 };
 
 use cw2::set_contract_version;
-use cw20::{Balance, Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw20::{
+    Balance, Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Denom,
+    TokenInfoResponse
+};
 
 use crate::error::ContractError;
 use crate::msg::{
-    CreateMsg, TopUpMsg, DetailsResponse, DetailsAllResponse, ExecuteMsg, InstantiateMsg, ListResponse, IsAdminResponse, QueryMsg, ReceiveMsg, ConstantMsg
+    AccessPassResponse, AllowlistMsg, QuoteResponse, WaitlistResponse, ClaimableResponse, GroupGateMsg, EncryptedUrlMsg, PositionResponse, CollaboratorResponse, CommissionResponse, CreateCommissionMsg, CreateMsg, EmissionResponse, PendingEmissionResponse, RewardsResponse, Payout, PayoutsResponse, RevenueSplitMsg, SplitResponse,
+    SplitShareMsg, ListingFeeMsg, ListingFeeResponse, ListingResponse, ListingsResponse, UnbondingClaimResponse, UnbondingResponse, TopUpMsg, DetailsResponse, DetailsAllResponse, ExecuteMsg, InstantiateMsg, ListResponse, IsAdminResponse, QueryMsg, ReceiveMsg, ConstantMsg,
+    PauseScope, PauseResponse, TokenResponse, TokensResponse, UpdateWorkMsg, WorkHistoryResponse
 };
-use crate::state::{all_escrow_ids, Escrow, GenericBalance, GenericAccount, ESCROWS, CONSTANT, AccountInfo};
+use crate::state::{
+    add_claimable, all_escrow_ids, denom_balance, AccessPassMetadata, Allowlist, GroupGate, WaitlistEntry, Commission, CommissionStatus,
+    Deliverable, Listing, Collaborator, Emission, Escrow, FieldChange, GenericAccount, GenericBalance, ListingFee, PauseState,
+    PendingTransfer, RevenueSplit, SplitLimits, SplitShare, StakeToken, UnbondingClaim, WorkEdit,
+    CLAIMABLE, DEFAULT_SPLIT, ESCROWS, CONSTANT, FEE_CREDITS, FEE_EXEMPT, LISTING_FEE, PAUSE, PENDING_TRANSFERS,
+    SPLIT_LIMITS, STAKE_TOKENS, TOTAL_BPS, TRANSFER_SEQ, UNBONDING, WORK_HISTORY, EMISSION,
+    STAKER_EMISSIONS, ACCESS_PASSES, ACCESS_PASS_NFT, ACCESS_PASS_SEQ, LISTINGS, LISTING_SEQ, SELLER_LISTINGS, MARKET_DENOMS,
+    COMMISSIONS, ENCRYPTED_URLS, STAKER_PUBKEYS, ALLOWLISTS,
+    MANAGER_GROUP, WAITLISTS
+};
+use cw4::Cw4Contract;
+use cw_storage_plus::Bound;
+use cw721_base::{ExecuteMsg as Cw721ExecuteMsg, MintMsg};
+use cw_utils::Expiration;
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "Doodle Workshop";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Longest lock, in seconds, that still adds to a staker's reward weight
+const MAX_REWARD_DURATION: u64 = 365 * 24 * 60 * 60;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
@@ -34,8 +55,34 @@ pub fn instantiate(
     CONSTANT.save(deps.storage, "min_stake", &String::from("10"))?;
     CONSTANT.save(deps.storage, "rate_client", &String::from("10"))?;
     CONSTANT.save(deps.storage, "rate_manager", &String::from("10"))?;
+    CONSTANT.save(deps.storage, "unbonding_period", &String::from("0"))?;
+    CONSTANT.save(deps.storage, "royalty_bps", &String::from("0"))?;
+    CONSTANT.save(deps.storage, "review_window", &String::from("259200"))?;
+    CONSTANT.save(deps.storage, "delivery_timeout", &String::from("2592000"))?;
+    CONSTANT.save(deps.storage, "flag_threshold_bps", &String::from("5000"))?;
 
     CONSTANT.save(deps.storage, "crew_address", &msg.crew_address)?;
+    PAUSE.save(deps.storage, &PauseState::default())?;
+
+    // matches the old 10% rate_manager
+    DEFAULT_SPLIT.save(deps.storage, &RevenueSplit {
+        client_bps: 9000,
+        manager_bps: 1000,
+        stakers_bps: 0,
+        extra_shares: vec![],
+    })?;
+    SPLIT_LIMITS.save(deps.storage, &SplitLimits {
+        min_manager_bps: 0,
+        max_client_bps: TOTAL_BPS,
+        max_stakers_bps: TOTAL_BPS,
+    })?;
+
+    let crew = deps.api.addr_validate(&msg.crew_address)?;
+    STAKE_TOKENS.save(deps.storage, &crew, &StakeToken {
+        min_stake: Uint128::zero(),
+        enabled: true,
+    })?;
+    EMISSION.save(deps.storage, &new_emission(crew, env.block.time.seconds()))?;
     Ok(Response::default())
 }
 
@@ -56,9 +103,144 @@ pub fn execute(
         }
         ExecuteMsg::Refund { id } => execute_refund(deps, env, info, id),
         ExecuteMsg::Remove { id } => execute_remove(deps, env, info, id),
+        ExecuteMsg::UpdateWork(msg) => execute_update_work(deps, env, info, msg),
+        ExecuteMsg::SetPayoutAddress { id, address } => {
+            execute_set_payout_address(deps, info, id, address)
+        }
+        ExecuteMsg::SlashBond { id } => execute_slash_bond(deps, env, info, id),
+        ExecuteMsg::WithdrawBond { id } => execute_withdraw_bond(deps, env, info, id),
+        ExecuteMsg::LeaveWaitlist { id } => execute_leave_waitlist(deps, info, id),
+        ExecuteMsg::SetAllowlist { id, allowlist } => execute_set_allowlist(deps, info, id, allowlist),
+        ExecuteMsg::UpdateAllowlist { id, add, remove } => {
+            execute_update_allowlist(deps, info, id, add, remove)
+        }
+        ExecuteMsg::RegisterPubkey { id, pubkey } => execute_register_pubkey(deps, info, id, pubkey),
+        ExecuteMsg::UploadEncryptedUrls { id, blobs } => {
+            execute_upload_encrypted_urls(deps, info, id, blobs)
+        }
+        ExecuteMsg::RevealWorkUrl { id, work_url } => execute_reveal_work_url(deps, info, id, work_url),
+        ExecuteMsg::SubmitDeliverable { id, url, content_hash } => {
+            execute_submit_deliverable(deps, env, info, id, url, content_hash)
+        }
+        ExecuteMsg::FlagDeliverable { id } => execute_flag_deliverable(deps, env, info, id),
+        ExecuteMsg::ListPosition { id, amount, price, denom } => {
+            execute_list_position(deps, info, id, amount, price, denom)
+        }
+        ExecuteMsg::CancelListing { listing_id } => execute_cancel_listing(deps, info, listing_id),
+        ExecuteMsg::BuyPosition { listing_id } => {
+            execute_buy_position(deps, env, listing_id, Balance::from(info.funds), &info.sender)
+        }
+        ExecuteMsg::TransferPosition { id, recipient, amount } => {
+            execute_transfer_position(deps, env, info, id, recipient, amount)
+        }
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
-        ExecuteMsg::SetConstant(msg) => execute_setconstant(deps, info, msg)
+        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::SetConstant(msg) => execute_setconstant(deps, info, msg),
+        ExecuteMsg::SetPause { scope, paused } => execute_setpause(deps, info, scope, paused),
+        ExecuteMsg::AddToken { address, min_stake, enabled } => {
+            execute_add_token(deps, info, address, min_stake, enabled)
+        }
+        ExecuteMsg::RemoveToken { address } => execute_remove_token(deps, info, address),
+        ExecuteMsg::SetListingFee { fee } => execute_set_listing_fee(deps, info, fee),
+        ExecuteMsg::PayListingFee {} => {
+            execute_pay_listing_fee(deps, Balance::from(info.funds), &info.sender)
+        }
+        ExecuteMsg::SetAccessPassNft { address } => execute_set_access_pass_nft(deps, info, address),
+        ExecuteMsg::SetDefaultSplit(msg) => execute_set_default_split(deps, info, msg),
+        ExecuteMsg::SetSplitLimits(limits) => execute_set_split_limits(deps, info, limits),
+        ExecuteMsg::SetManagerGroup { group } => execute_set_manager_group(deps, info, group),
+        ExecuteMsg::SetMarketDenom { denom, enabled } => {
+            execute_set_market_denom(deps, info, denom, enabled)
+        }
+        ExecuteMsg::CreateCommission(msg) => {
+            execute_create_commission(deps, env, msg, Balance::from(info.funds), &info.sender)
+        }
+        ExecuteMsg::SubmitCommission { id, url, content_hash } => {
+            execute_submit_commission(deps, env, info, id, url, content_hash)
+        }
+        ExecuteMsg::ApproveCommission { id } => execute_review_commission(deps, env, info, id, true),
+        ExecuteMsg::RejectCommission { id } => execute_review_commission(deps, env, info, id, false),
+        ExecuteMsg::ClaimCommissionTimeout { id } => execute_commission_timeout(deps, env, id),
+        ExecuteMsg::AddFeeExemption { address } => execute_fee_exemption(deps, info, address, true),
+        ExecuteMsg::RemoveFeeExemption { address } => {
+            execute_fee_exemption(deps, info, address, false)
+        }
+    }
+}
+
+/// Only SetConstant is open to anyone until a manager is configured,
+/// so the deployer can name one. Everything else uses check_configured_manager
+fn check_manager(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if manager_configured(deps)? && !is_manager(deps, sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Whether a manager_addr or a manager group is set
+fn manager_configured(deps: Deps) -> StdResult<bool> {
+    Ok(!CONSTANT.load(deps.storage, "manager_addr")?.is_empty() || MANAGER_GROUP.may_load(deps.storage)?.is_some())
+}
+
+/// Where the manager share goes: manager_addr, or the admin of the manager
+/// group while no manager is configured. None when neither is set
+fn manager_payee(deps: Deps) -> StdResult<Option<Addr>> {
+    let manager_addr = CONSTANT.load(deps.storage, "manager_addr")?;
+    if !manager_addr.is_empty() {
+        return Ok(Some(deps.api.addr_validate(&manager_addr)?));
+    }
+    Ok(match MANAGER_GROUP.may_load(deps.storage)? {
+        Some(group) => match Cw4Contract::new(group).admin(&deps.querier)? {
+            Some(admin) => Some(deps.api.addr_validate(&admin)?),
+            None => None,
+        },
+        None => None,
+    })
+}
+
+/// Like check_manager, but closed while neither a manager nor a manager group is configured
+fn check_configured_manager(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if !is_manager(deps, sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// The configured manager, or the admin of the manager group
+fn is_manager(deps: Deps, sender: &Addr) -> StdResult<bool> {
+    if CONSTANT.load(deps.storage, "manager_addr")? == sender.as_str() {
+        return Ok(true);
+    }
+    Ok(match MANAGER_GROUP.may_load(deps.storage)? {
+        Some(group) => Cw4Contract::new(group).admin(&deps.querier)?.as_deref() == Some(sender.as_str()),
+        None => false,
+    })
+}
+
+/// Fails unless the token is registered and enabled
+fn check_stake_token(deps: Deps, token: &Addr) -> Result<StakeToken, ContractError> {
+    let stake_token = STAKE_TOKENS
+        .may_load(deps.storage, token)?
+        .ok_or(ContractError::TokenNotRegistered {})?;
+    if !stake_token.enabled {
+        return Err(ContractError::TokenDisabled {});
+    }
+    Ok(stake_token)
+}
+
+fn check_not_paused(deps: Deps, scope: PauseScope) -> Result<(), ContractError> {
+    let pause = PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    let (paused, name) = match scope {
+        // any paused path counts, matching SetPause setting all three at once
+        PauseScope::All => (pause.create || pause.top_up || pause.receive, "all"),
+        PauseScope::Create => (pause.create, "create"),
+        PauseScope::TopUp => (pause.top_up, "top_up"),
+        PauseScope::Receive => (pause.receive, "receive"),
+    };
+    if paused {
+        return Err(ContractError::Paused { scope: name.to_string() });
     }
+    Ok(())
 }
 
 pub fn execute_setconstant(
@@ -66,24 +248,338 @@ pub fn execute_setconstant(
     info: MessageInfo,
     msg: ConstantMsg,
 ) -> Result<Response, ContractError> {
-    
-    let manager_addr:String = CONSTANT.load(deps.storage, "manager_addr")?;
-    let maddr:Addr;
-    if manager_addr.ne("") {
-        maddr = deps.api.addr_validate(&manager_addr)?;
-        if info.sender != maddr {
-            return Err(ContractError::Unauthorized {});
-        }
-    }
+    check_manager(deps.as_ref(), &info.sender)?;
     CONSTANT.save(deps.storage, "manager_addr", &msg.manager_addr)?;
     CONSTANT.save(deps.storage, "min_stake", &msg.min_stake)?;
     CONSTANT.save(deps.storage, "rate_client", &msg.rate_client)?;
     CONSTANT.save(deps.storage, "rate_manager", &msg.rate_manager)?;
+    if let Some(period) = msg.unbonding_period {
+        period.parse::<u64>().map_err(|e| StdError::parse_err("u64", e))?;
+        CONSTANT.save(deps.storage, "unbonding_period", &period)?;
+    }
+    if let Some(bps) = msg.royalty_bps {
+        if bps.parse::<u16>().map_err(|e| StdError::parse_err("u16", e))? > TOTAL_BPS {
+            return Err(ContractError::InvalidRoyalty {});
+        }
+        CONSTANT.save(deps.storage, "royalty_bps", &bps)?;
+    }
+    if let Some(window) = msg.review_window {
+        window.parse::<u64>().map_err(|e| StdError::parse_err("u64", e))?;
+        CONSTANT.save(deps.storage, "review_window", &window)?;
+    }
+    if let Some(timeout) = msg.delivery_timeout {
+        timeout.parse::<u64>().map_err(|e| StdError::parse_err("u64", e))?;
+        CONSTANT.save(deps.storage, "delivery_timeout", &timeout)?;
+    }
+    if let Some(bps) = msg.flag_threshold_bps {
+        if bps.parse::<u16>().map_err(|e| StdError::parse_err("u16", e))? > TOTAL_BPS {
+            return Err(ContractError::InvalidFlagThreshold {});
+        }
+        CONSTANT.save(deps.storage, "flag_threshold_bps", &bps)?;
+    }
 
     let res = Response::new().add_attributes(vec![("action", "setcontant")]);
     Ok(res)
 }
 
+pub fn execute_setpause(
+    deps: DepsMut,
+    info: MessageInfo,
+    scope: PauseScope,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    let mut pause = PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    match scope {
+        PauseScope::All => {
+            pause.create = paused;
+            pause.top_up = paused;
+            pause.receive = paused;
+        }
+        PauseScope::Create => pause.create = paused,
+        PauseScope::TopUp => pause.top_up = paused,
+        PauseScope::Receive => pause.receive = paused,
+    }
+    PAUSE.save(deps.storage, &pause)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "setpause")
+        .add_attribute("paused", paused.to_string()))
+}
+
+
+pub fn execute_add_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    min_stake: Uint128,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    let token = deps.api.addr_validate(&address)?;
+    STAKE_TOKENS.save(deps.storage, &token, &StakeToken { min_stake, enabled })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_token")
+        .add_attribute("token", token))
+}
+
+pub fn execute_remove_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    let token = deps.api.addr_validate(&address)?;
+    if !STAKE_TOKENS.has(deps.storage, &token) {
+        return Err(ContractError::TokenNotRegistered {});
+    }
+    STAKE_TOKENS.remove(deps.storage, &token);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_token")
+        .add_attribute("token", token))
+}
+
+pub fn execute_set_listing_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee: Option<ListingFeeMsg>,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    match fee {
+        Some(fee) => {
+            let denom = match fee.denom {
+                Denom::Native(denom) => Denom::Native(denom),
+                Denom::Cw20(address) => {
+                    let address = deps.api.addr_validate(address.as_str())?;
+                    if address != CONSTANT.load(deps.storage, "crew_address")? {
+                        return Err(ContractError::InvalidFeeDenom {});
+                    }
+                    Denom::Cw20(address)
+                }
+            };
+            LISTING_FEE.save(deps.storage, &ListingFee {
+                amount: fee.amount,
+                denom,
+                collector: deps.api.addr_validate(&fee.collector)?,
+            })?;
+        }
+        None => LISTING_FEE.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attribute("action", "set_listing_fee"))
+}
+
+pub fn execute_pay_listing_fee(
+    deps: DepsMut,
+    balance: Balance,
+    sender: &Addr,
+) -> Result<Response, ContractError> {
+    let fee = match LISTING_FEE.may_load(deps.storage)? {
+        Some(fee) if !fee.amount.is_zero() => fee,
+        _ => return Err(ContractError::NoListingFee {}),
+    };
+    let mut paid = GenericBalance::default();
+    paid.try_add(balance)?;
+    let fee_balance = fee.balance();
+    if paid != fee_balance {
+        return Err(ContractError::ListingFeeNotPaid {});
+    }
+
+    let credits = FEE_CREDITS.may_load(deps.storage, sender)?.unwrap_or_default();
+    FEE_CREDITS.save(deps.storage, sender, &(credits + 1))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pay_listing_fee")
+        .add_attribute("from", sender.clone())
+        .add_submessages(send_tokens(&fee.collector, &fee_balance)?))
+}
+
+pub fn execute_set_default_split(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: RevenueSplitMsg,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    let limits = SPLIT_LIMITS.load(deps.storage)?;
+    let split = validate_split(deps.as_ref(), msg, &limits)?;
+    DEFAULT_SPLIT.save(deps.storage, &split)?;
+
+    Ok(Response::new().add_attribute("action", "set_default_split"))
+}
+
+pub fn execute_set_split_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    limits: SplitLimits,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    // the default split has to stay usable under the new limits
+    let default = DEFAULT_SPLIT.load(deps.storage)?;
+    check_split_limits(&default, &limits)?;
+    SPLIT_LIMITS.save(deps.storage, &limits)?;
+
+    Ok(Response::new().add_attribute("action", "set_split_limits"))
+}
+
+fn validate_split(
+    deps: Deps,
+    msg: RevenueSplitMsg,
+    limits: &SplitLimits,
+) -> Result<RevenueSplit, ContractError> {
+    let extra_shares: StdResult<Vec<SplitShare>> = msg
+        .extra_shares
+        .into_iter()
+        .map(|share| {
+            Ok(SplitShare {
+                address: deps.api.addr_validate(&share.address)?,
+                bps: share.bps,
+            })
+        })
+        .collect();
+    let split = RevenueSplit {
+        client_bps: msg.client_bps,
+        manager_bps: msg.manager_bps,
+        stakers_bps: msg.stakers_bps,
+        extra_shares: extra_shares?,
+    };
+    if split.total_bps() != TOTAL_BPS as u32 {
+        return Err(ContractError::InvalidSplit {});
+    }
+    check_split_limits(&split, limits)?;
+    Ok(split)
+}
+
+fn check_split_limits(split: &RevenueSplit, limits: &SplitLimits) -> Result<(), ContractError> {
+    if split.manager_bps < limits.min_manager_bps
+        || split.client_bps > limits.max_client_bps
+        || split.stakers_bps > limits.max_stakers_bps
+    {
+        return Err(ContractError::SplitOutOfBounds {});
+    }
+    Ok(())
+}
+
+fn split_to_msg(split: RevenueSplit) -> RevenueSplitMsg {
+    RevenueSplitMsg {
+        client_bps: split.client_bps,
+        manager_bps: split.manager_bps,
+        stakers_bps: split.stakers_bps,
+        extra_shares: split
+            .extra_shares
+            .into_iter()
+            .map(|share| SplitShareMsg {
+                address: share.address.into(),
+                bps: share.bps,
+            })
+            .collect(),
+    }
+}
+
+pub fn execute_set_access_pass_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    match address {
+        Some(address) => ACCESS_PASS_NFT.save(deps.storage, &deps.api.addr_validate(&address)?)?,
+        None => ACCESS_PASS_NFT.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attribute("action", "set_access_pass_nft"))
+}
+
+pub fn execute_set_manager_group(
+    deps: DepsMut,
+    info: MessageInfo,
+    group: Option<String>,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    match group {
+        Some(group) => MANAGER_GROUP.save(deps.storage, &deps.api.addr_validate(&group)?)?,
+        None => MANAGER_GROUP.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attribute("action", "set_manager_group"))
+}
+
+pub fn execute_set_market_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    if enabled {
+        MARKET_DENOMS.save(deps.storage, &denom, &true)?;
+    } else {
+        MARKET_DENOMS.remove(deps.storage, &denom);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_market_denom")
+        .add_attribute("denom", denom)
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+pub fn execute_fee_exemption(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), &info.sender)?;
+
+    let addr = deps.api.addr_validate(&address)?;
+    if exempt {
+        FEE_EXEMPT.save(deps.storage, &addr, &true)?;
+    } else {
+        FEE_EXEMPT.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "fee_exemption")
+        .add_attribute("address", addr)
+        .add_attribute("exempt", exempt.to_string()))
+}
+
+/// Uses a prepaid fee if there is one, else takes the fee out of the create
+/// deposit and returns the transfer forwarding it to the collector
+fn charge_listing_fee(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    deposit: &mut GenericBalance,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let fee = match LISTING_FEE.may_load(storage)? {
+        Some(fee) if !fee.amount.is_zero() => fee,
+        _ => return Ok(vec![]),
+    };
+    if FEE_EXEMPT.has(storage, sender) {
+        return Ok(vec![]);
+    }
+    let credits = FEE_CREDITS.may_load(storage, sender)?.unwrap_or_default();
+    if credits > 0 {
+        FEE_CREDITS.save(storage, sender, &(credits - 1))?;
+        return Ok(vec![]);
+    }
+
+    let fee_balance = fee.balance();
+    deposit
+        .try_sub_balance(&fee_balance)
+        .map_err(|_| ContractError::ListingFeeNotPaid {})?;
+    Ok(send_tokens(&fee.collector, &fee_balance)?)
+}
 
 pub fn execute_receive(
     deps: DepsMut,
@@ -91,56 +587,99 @@ pub fn execute_receive(
     info: MessageInfo,
     wrapper: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
+    check_not_paused(deps.as_ref(), PauseScope::Receive)?;
     let msg: ReceiveMsg = from_binary(&wrapper.msg)?;
     let balance = Balance::Cw20(Cw20CoinVerified {
         address: info.sender.clone(),
         amount: wrapper.amount,
     });
-    let str = CONSTANT.load(deps.storage, "crew_address")?;
-    let addr = deps.api.addr_validate(&str)?;
-    if info.sender.clone() != addr {
-        return Err(ContractError::NotCrew {  });
-    }
     let api = deps.api;
+    // the registry only gates new works, running works keep their token
     match msg {
         ReceiveMsg::Create(msg) => {
+            check_stake_token(deps.as_ref(), &info.sender)?;
             execute_create(deps, env, msg, balance, &api.addr_validate(&wrapper.sender)?)
         }
         ReceiveMsg::TopUp(msg ) => {
             execute_top_up(deps, env, msg, balance, &api.addr_validate(&wrapper.sender)?)
         }
+        ReceiveMsg::FundRewards { id } => {
+            execute_fund_rewards(deps, id, balance, &api.addr_validate(&wrapper.sender)?)
+        }
+        ReceiveMsg::FundEmission { duration } => {
+            execute_fund_emission(deps, env, duration, balance, &api.addr_validate(&wrapper.sender)?)
+        }
+        ReceiveMsg::BuyPosition { listing_id } => {
+            execute_buy_position(deps, env, listing_id, balance, &api.addr_validate(&wrapper.sender)?)
+        }
+        ReceiveMsg::PayListingFee {} => {
+            execute_pay_listing_fee(deps, balance, &api.addr_validate(&wrapper.sender)?)
+        }
+        ReceiveMsg::CreateCommission(msg) => {
+            check_stake_token(deps.as_ref(), &info.sender)?;
+            execute_create_commission(deps, env, msg, balance, &api.addr_validate(&wrapper.sender)?)
+        }
     }
 }
 
 pub fn execute_create(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
     msg: CreateMsg,
     balance: Balance,
     sender: &Addr,
 ) -> Result<Response, ContractError> {
+    check_not_paused(deps.as_ref(), PauseScope::Create)?;
     if balance.is_empty() {
         return Err(ContractError::EmptyBalance {});
     }
 
-    let mut cw20_whitelist = msg.addr_whitelist(deps.api)?;
+    let stake_token = match (&msg.stake_token, &balance) {
+        (Some(token), _) => deps.api.addr_validate(token)?,
+        (None, Balance::Cw20(token)) => token.address.clone(),
+        (None, Balance::Native(_)) => {
+            deps.api.addr_validate(&CONSTANT.load(deps.storage, "crew_address")?)?
+        }
+    };
+    check_stake_token(deps.as_ref(), &stake_token)?;
+    let token_info: TokenInfoResponse = deps
+        .querier
+        .query_wasm_smart(stake_token.clone(), &Cw20QueryMsg::TokenInfo {})?;
 
-    let escrow_balance = match balance {
+    let mut client_bond = match balance {
         Balance::Native(balance) => GenericBalance {
             native: balance.0,
             cw20: vec![],
         },
-        Balance::Cw20(token) => {
-
-            if !cw20_whitelist.iter().any(|t| t == &token.address) {
-                cw20_whitelist.push(token.address.clone())
-            }
-            GenericBalance {
-                native: vec![],
-                cw20: vec![token],
-            }
+        Balance::Cw20(token) => GenericBalance {
+            native: vec![],
+            cw20: vec![token],
+        },
+    };
+    let fee_msgs = charge_listing_fee(deps.storage, sender, &mut client_bond)?;
+    let arbiter = match msg.arbiter {
+        Some(arbiter) => Some(deps.api.addr_validate(&arbiter)?),
+        None => None,
+    };
+    let split = match msg.split {
+        Some(split) => {
+            let limits = SPLIT_LIMITS.load(deps.storage)?;
+            validate_split(deps.as_ref(), split, &limits)?
         }
+        None => DEFAULT_SPLIT.load(deps.storage)?,
     };
+    let mut collaborators: Vec<Collaborator> = vec![];
+    for collaborator in msg.collaborators.unwrap_or_default() {
+        let member = deps.api.addr_validate(&collaborator.address)?;
+        if collaborator.weight == 0 || collaborators.iter().any(|c| c.member == member) {
+            return Err(ContractError::InvalidCollaborators {});
+        }
+        collaborators.push(Collaborator {
+            payout: member.clone(),
+            member,
+            weight: collaborator.weight,
+        });
+    }
 
     // let account_info = GenericAccount {
     //     account: vec![]
@@ -151,270 +690,1945 @@ pub fn execute_create(
         account_info: String::from(""),
         work_title: msg.work_title,
         work_desc: msg.work_desc,
-        work_url: msg.work_url,
+        work_url: String::from(""),
+        work_url_hash: check_hash(&msg.work_url_hash)?,
         start_time: msg.start_time,
         account_min_stake_amount: msg.account_min_stake_amount,
         stake_amount: msg.stake_amount,
-        balance: escrow_balance,
-        cw20_whitelist,
+        balance: GenericBalance::default(),
+        reward_pool: GenericBalance::default(),
+        client_bond,
+        arbiter,
+        split,
+        collaborators,
+        stake_token,
+        stake_decimals: token_info.decimals,
+        stake_symbol: token_info.symbol,
         state: 0, // created state
-        image_url: msg.image_url
+        image_url: msg.image_url,
+        unbonding_period: msg.unbonding_period,
+        group: match msg.group {
+            Some(GroupGateMsg { address, stake_per_weight }) => Some(GroupGate {
+                address: deps.api.addr_validate(&address)?,
+                stake_per_weight,
+            }),
+            None => None,
+        },
+        max_stake_per_address: msg.max_stake_per_address,
+        max_stakers: msg.max_stakers,
+        pricing: msg.pricing,
+        allowlist: save_allowlist(deps.storage, deps.api, &msg.id, msg.allowlist)?,
+        funded_at: None,
+        deliverable: None,
+        flagged_by: vec![],
+        closed: false,
     };
 
     // try to store it, fail if the id was already in use
+    if COMMISSIONS.has(deps.storage, &msg.id) {
+        return Err(ContractError::AlreadyInUse {});
+    }
     ESCROWS.update(deps.storage, &msg.id, |existing| match existing {
         None => Ok(escrow),
         Some(_) => Err(ContractError::AlreadyInUse {}),
     })?;
 
-    let res = Response::new().add_attributes(vec![("action", "create"), ("id", msg.id.as_str())]);
+    let res = Response::new()
+        .add_attributes(vec![("action", "create"), ("id", msg.id.as_str())])
+        .add_submessages(fee_msgs);
     Ok(res)
 }
 
-pub fn execute_top_up(
+pub fn execute_create_commission(
     deps: DepsMut,
     env: Env,
-    msg: TopUpMsg,
+    msg: CreateCommissionMsg,
     balance: Balance,
-    sender: &Addr
+    sender: &Addr,
 ) -> Result<Response, ContractError> {
+    check_not_paused(deps.as_ref(), PauseScope::Create)?;
     if balance.is_empty() {
         return Err(ContractError::EmptyBalance {});
     }
-    // this fails is no escrow there
-    let mut escrow = ESCROWS.load(deps.storage, &msg.id)?;
+    if ESCROWS.has(deps.storage, &msg.id) {
+        return Err(ContractError::AlreadyInUse {});
+    }
+    let arbiter = match msg.arbiter {
+        Some(arbiter) => Some(deps.api.addr_validate(&arbiter)?),
+        None => None,
+    };
+    let mut payment = GenericBalance::default();
+    payment.try_add(balance)?;
+    // a submission at the deadline must still have a reachable review end
+    let submit_deadline = env
+        .block
+        .time
+        .seconds()
+        .checked_add(msg.submit_deadline)
+        .ok_or(ContractError::InvalidDeadline {})?;
+    submit_deadline
+        .checked_add(msg.review_period)
+        .ok_or(ContractError::InvalidDeadline {})?;
+
+    let commission = Commission {
+        client: sender.clone(),
+        artist: deps.api.addr_validate(&msg.artist)?,
+        arbiter,
+        work_title: msg.work_title,
+        work_desc: msg.work_desc,
+        balance: payment,
+        submit_deadline,
+        review_period: msg.review_period,
+        deliverable: None,
+        status: CommissionStatus::Open,
+    };
+    COMMISSIONS.update(deps.storage, &msg.id, |existing| match existing {
+        None => Ok(commission),
+        Some(_) => Err(ContractError::AlreadyInUse {}),
+    })?;
+
+    Ok(Response::new().add_attributes(vec![("action", "create_commission"), ("id", msg.id.as_str())]))
+}
+
+pub fn execute_submit_commission(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    url: String,
+    content_hash: String,
+) -> Result<Response, ContractError> {
+    let mut commission = COMMISSIONS.load(deps.storage, &id)?;
+    if commission.artist != info.sender {
+        return Err(ContractError::NotArtist {});
+    }
+    if commission.status != CommissionStatus::Open {
+        return Err(ContractError::WrongCommissionStatus { status: "open".to_string() });
+    }
+    let now = env.block.time.seconds();
+    if now > commission.submit_deadline {
+        return Err(ContractError::DeadlinePassed {});
+    }
+
+    commission.deliverable = Some(Deliverable {
+        url,
+        content_hash,
+        submitted_at: now,
+        review_deadline: now
+            .checked_add(commission.review_period)
+            .ok_or(ContractError::InvalidDeadline {})?,
+    });
+    commission.status = CommissionStatus::Submitted;
+    COMMISSIONS.save(deps.storage, &id, &commission)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "submit_commission")
+        .add_attribute("id", id))
+}
+
+/// Pays the artist on approval, refunds the client on rejection.
+/// Once the artist submitted, a rejection is up to the arbiter if there is one
+pub fn execute_review_commission(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    let commission = COMMISSIONS.load(deps.storage, &id)?;
+    if !commission.can_review(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let status = match (&commission.status, approve) {
+        (CommissionStatus::Paid, _) | (CommissionStatus::Refunded, _) => {
+            return Err(ContractError::AlreadySettled {})
+        }
+        (CommissionStatus::Open, true) => {
+            return Err(ContractError::WrongCommissionStatus { status: "submitted".to_string() })
+        }
+        // the artist keeps the whole submission period
+        (CommissionStatus::Open, false) if env.block.time.seconds() <= commission.submit_deadline => {
+            return Err(ContractError::NotTimedOut {})
+        }
+        (CommissionStatus::Submitted, false)
+            if commission.arbiter.is_some() && commission.arbiter.as_ref() != Some(&info.sender) =>
+        {
+            return Err(ContractError::Unauthorized {})
+        }
+        (_, true) => CommissionStatus::Paid,
+        (_, false) => CommissionStatus::Refunded,
+    };
+
+    settle_commission(deps, id, commission, status, "review_commission")
+}
+
+pub fn execute_commission_timeout(
+    deps: DepsMut,
+    env: Env,
+    id: String,
+) -> Result<Response, ContractError> {
+    let commission = COMMISSIONS.load(deps.storage, &id)?;
+    let now = env.block.time.seconds();
+    let status = match (&commission.status, &commission.deliverable) {
+        (CommissionStatus::Open, _) if now > commission.submit_deadline => CommissionStatus::Refunded,
+        (CommissionStatus::Submitted, Some(deliverable)) if now >= deliverable.review_deadline => {
+            CommissionStatus::Paid
+        }
+        (CommissionStatus::Open, _) | (CommissionStatus::Submitted, _) => {
+            return Err(ContractError::NotTimedOut {})
+        }
+        _ => return Err(ContractError::AlreadySettled {}),
+    };
+
+    settle_commission(deps, id, commission, status, "commission_timeout")
+}
+
+fn settle_commission(
+    deps: DepsMut,
+    id: String,
+    mut commission: Commission,
+    status: CommissionStatus,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let to = match status {
+        CommissionStatus::Paid => commission.artist.clone(),
+        _ => commission.client.clone(),
+    };
+    let messages = send_tokens_or_claim(deps.storage, &to, &commission.balance)?;
+    commission.balance = GenericBalance::default();
+    commission.status = status;
+    COMMISSIONS.save(deps.storage, &id, &commission)?;
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("id", id)
+        .add_attribute("to", to)
+        .add_submessages(messages))
+}
+
+pub fn execute_top_up(
+    deps: DepsMut,
+    env: Env,
+    msg: TopUpMsg,
+    balance: Balance,
+    sender: &Addr
+) -> Result<Response, ContractError> {
+    check_not_paused(deps.as_ref(), PauseScope::TopUp)?;
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &msg.id)?;
+
+    if escrow.is_expired(&env) && escrow.state > 0 {
+        return Err(ContractError::StakeFinished {});
+    }
+    if escrow.closed {
+        return Err(ContractError::WorkClosed {});
+    }
+
+
+    let cwval:Uint128 = match &balance {
+        // ensure the token is the one this work is staked in
+        Balance::Cw20(token) if token.address == escrow.stake_token => {
+            let min_stake = match STAKE_TOKENS.may_load(deps.storage, &token.address)? {
+                Some(stake_token) if !stake_token.enabled => return Err(ContractError::TokenDisabled {}),
+                Some(stake_token) => stake_token.min_stake,
+                None => Uint128::zero(),
+            };
+            if token.amount < min_stake {
+                return Err(ContractError::InsufficientTopUp {});
+            }
+            token.amount
+        }
+        _ => return Err(ContractError::NotInWhitelist {}),
+    };
+    check_allowlist(deps.as_ref(), &msg.id, &escrow, sender, msg.proof.as_deref())?;
+    check_group(deps.as_ref(), &escrow, sender, cwval)?;
+
+    let totals = GenericAccount::parse(&escrow.account_info)?.totals();
+    let is_staker = totals.iter().any(|(staker, _)| staker == sender);
+    if !is_staker {
+        if let Some(pricing) = &escrow.pricing {
+            if cwval < pricing.price(totals.len() as u32)? {
+                return Err(ContractError::InsufficientTopUp {});
+            }
+        }
+    }
+
+    // a full work queues newcomers instead of turning them away
+    if let Some(max_stakers) = escrow.max_stakers {
+        if !is_staker && totals.len() >= max_stakers as usize {
+            if escrow.max_stake_per_address.is_some_and(|max| cwval > max) {
+                return Err(ContractError::StakeCapExceeded {});
+            }
+            let mut waitlist = WAITLISTS.may_load(deps.storage, &msg.id)?.unwrap_or_default();
+            waitlist.push(WaitlistEntry {
+                addr: sender.clone(),
+                amount: cwval,
+                start_time: msg.start_time,
+                end_time: msg.end_time,
+            });
+            WAITLISTS.save(deps.storage, &msg.id, &waitlist)?;
+            return Ok(Response::new().add_attributes(vec![
+                ("action", "join_waitlist"),
+                ("id", msg.id.as_str()),
+                ("position", waitlist.len().to_string().as_str()),
+            ]));
+        }
+    }
+    
+    // let account_info:AccountInfo = AccountInfo {
+    //     addr: sender.clone(),
+    //     amount: cwval,
+    //     start_time: msg.start_time,
+    //     end_time: msg.end_time
+    // };
+
+    let str:String = escrow.account_info + ";" + 
+    sender.to_string().as_str() + ":" +
+    cwval.to_string().as_str() + ":" +
+    msg.start_time.to_string().as_str() + ":" +
+    msg.end_time.to_string().as_str() + ":" +
+    env.block.time.seconds().to_string().as_str();
+    escrow.account_info = str;
+    check_capacity(&escrow)?;
+    // escrow.account_info.add_account(account_info);
+    escrow.balance.try_add(balance)?;
+    change_emission_stake(deps.storage, &env, &escrow.stake_token, sender, cwval, true)?;
+    
+    if escrow.staked() >= escrow.stake_amount /*&& escrow.is_expired(&env)*/ {
+        escrow.state = 1; //set to started state
+        escrow.funded_at.get_or_insert(env.block.time.seconds());
+    }
+    let mint_msgs = if escrow.state == 1 {
+        mint_access_passes(deps.storage, &msg.id, &escrow)?
+    } else {
+        vec![]
+    };
+    // and save
+    ESCROWS.save(deps.storage, &msg.id, &escrow)?;
+    //return Err(ContractError::NotInWhitelist {});
+    let res = Response::new()
+        .add_attributes(vec![("action", "top_up"), ("id", msg.id.as_str())])
+        .add_submessages(mint_msgs);
+    Ok(res)
+}
+
+pub fn execute_fund_rewards(
+    deps: DepsMut,
+    id: String,
+    balance: Balance,
+    sender: &Addr,
+) -> Result<Response, ContractError> {
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.state > 1 {
+        return Err(ContractError::AlreadySettled {});
+    }
+    if escrow.closed {
+        return Err(ContractError::WorkClosed {});
+    }
+    if let Balance::Cw20(token) = &balance {
+        if token.address != escrow.stake_token {
+            check_stake_token(deps.as_ref(), &token.address)?;
+        }
+    }
+
+    escrow.reward_pool.try_add(balance)?;
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_rewards")
+        .add_attribute("id", id)
+        .add_attribute("from", sender))
+}
+
+pub fn execute_fund_emission(
+    deps: DepsMut,
+    env: Env,
+    duration: u64,
+    balance: Balance,
+    sender: &Addr,
+) -> Result<Response, ContractError> {
+    check_configured_manager(deps.as_ref(), sender)?;
+    if duration == 0 {
+        return Err(ContractError::InvalidDuration {});
+    }
+    let now = env.block.time.seconds();
+    let mut emission = load_emission(deps.storage, now)?;
+    let amount = match balance {
+        Balance::Cw20(token) if token.address == emission.reward_token => token.amount,
+        _ => return Err(ContractError::WrongRewardToken {}),
+    };
+
+    emission.update(now)?;
+    let leftover = if now < emission.period_finish {
+        emission.rate.checked_mul(Uint128::from(emission.period_finish - now)).map_err(StdError::from)?
+    } else {
+        Uint128::zero()
+    };
+    emission.rate = amount.checked_add(leftover).map_err(StdError::from)? / Uint128::from(duration);
+    emission.period_finish = now.checked_add(duration).ok_or(ContractError::InvalidDuration {})?;
+    emission.last_update = now;
+    EMISSION.save(deps.storage, &emission)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_emission")
+        .add_attribute("rate", emission.rate)
+        .add_attribute("period_finish", emission.period_finish.to_string()))
+}
+
+fn new_emission(crew: Addr, now: u64) -> Emission {
+    Emission {
+        reward_token: crew.clone(),
+        stake_token: crew,
+        rate: Uint128::zero(),
+        period_finish: now,
+        last_update: now,
+        reward_per_token: Decimal::zero(),
+        total_staked: Uint128::zero(),
+    }
+}
+
+fn load_emission(storage: &dyn Storage, now: u64) -> StdResult<Emission> {
+    match EMISSION.may_load(storage)? {
+        Some(emission) => Ok(emission),
+        None => {
+            let crew = CONSTANT.load(storage, "crew_address")?;
+            Ok(new_emission(Addr::unchecked(crew), now))
+        }
+    }
+}
+
+/// Called on every change of an active stake so the accumulator is
+/// always settled at the old weight before the new one applies
+fn change_emission_stake(
+    storage: &mut dyn Storage,
+    env: &Env,
+    stake_token: &Addr,
+    staker: &Addr,
+    amount: Uint128,
+    increase: bool,
+) -> Result<(), ContractError> {
+    let now = env.block.time.seconds();
+    let mut emission = load_emission(storage, now)?;
+    if *stake_token != emission.stake_token || amount.is_zero() {
+        return Ok(());
+    }
+    emission.update(now)?;
+
+    let mut info = STAKER_EMISSIONS.may_load(storage, staker)?.unwrap_or_default();
+    info.settle(emission.reward_per_token)?;
+    if increase {
+        info.staked = info.staked.checked_add(amount).map_err(StdError::from)?;
+        emission.total_staked = emission.total_staked.checked_add(amount).map_err(StdError::from)?;
+    } else {
+        info.staked = info.staked.checked_sub(amount).map_err(StdError::from)?;
+        emission.total_staked = emission.total_staked.checked_sub(amount).map_err(StdError::from)?;
+    }
+
+    STAKER_EMISSIONS.save(storage, staker, &info)?;
+    EMISSION.save(storage, &emission)?;
+    Ok(())
+}
+
+/// Moves the emission rewards earned so far into the claimable balance
+fn settle_emission(storage: &mut dyn Storage, env: &Env, staker: &Addr) -> Result<(), ContractError> {
+    let mut info = match STAKER_EMISSIONS.may_load(storage, staker)? {
+        Some(info) => info,
+        None => return Ok(()),
+    };
+    let now = env.block.time.seconds();
+    let mut emission = load_emission(storage, now)?;
+    emission.update(now)?;
+    info.settle(emission.reward_per_token)?;
+
+    if !info.pending.is_zero() {
+        let mut earned = GenericBalance::default();
+        earned.try_add(Balance::Cw20(Cw20CoinVerified {
+            address: emission.reward_token.clone(),
+            amount: info.pending,
+        }))?;
+        add_claimable(storage, staker, &earned)?;
+        info.pending = Uint128::zero();
+    }
+    STAKER_EMISSIONS.save(storage, staker, &info)?;
+    EMISSION.save(storage, &emission)?;
+    Ok(())
+}
+
+/// Reward weight of every staker: amount times the seconds the contract has seen it staked,
+/// up to `now`. The duration is capped at MAX_REWARD_DURATION and entries without a recorded
+/// stake time count for one second
+fn reward_weights(escrow: &Escrow, now: u64) -> StdResult<Vec<(Addr, Uint256)>> {
+    let mut weights: Vec<(Addr, Uint256)> = vec![];
+    for account in GenericAccount::parse(&escrow.account_info)?.account {
+        let duration = if account.staked_at == 0 {
+            1
+        } else {
+            now.saturating_sub(account.staked_at).clamp(1, MAX_REWARD_DURATION)
+        };
+        let weight = account.amount.full_mul(duration);
+        match weights.iter_mut().find(|(addr, _)| *addr == account.addr) {
+            Some((_, total)) => *total = total.checked_add(weight)?,
+            None => weights.push((account.addr, weight)),
+        }
+    }
+    Ok(weights)
+}
+
+/// Mints a pass to every staker of a funded work that has none yet,
+/// so late stakers get theirs on their own top up
+fn mint_access_passes(storage: &mut dyn Storage, id: &str, escrow: &Escrow) -> StdResult<Vec<SubMsg>> {
+    let nft_contract = match ACCESS_PASS_NFT.may_load(storage)? {
+        Some(nft_contract) => nft_contract,
+        None => return Ok(vec![]),
+    };
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    for (staker, amount) in GenericAccount::parse(&escrow.account_info)?.totals() {
+        if ACCESS_PASSES.has(storage, (id, &staker)) {
+            continue;
+        }
+        let seq = ACCESS_PASS_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+        ACCESS_PASS_SEQ.save(storage, &seq)?;
+        let token_id = format!("{}/{}", id, seq);
+        ACCESS_PASSES.save(storage, (id, &staker), &token_id)?;
+
+        let mint = Cw721ExecuteMsg::Mint(MintMsg {
+            token_id,
+            owner: staker.into(),
+            token_uri: None,
+            extension: AccessPassMetadata {
+                work_id: id.to_string(),
+                amount,
+            },
+        });
+        msgs.push(SubMsg::new(WasmMsg::Execute {
+            contract_addr: nft_contract.to_string(),
+            msg: to_binary(&mint)?,
+            funds: vec![],
+        }));
+    }
+    Ok(msgs)
+}
+
+/// Moves the pass of a staker leaving id to the address taking over the position,
+/// or burns it when that address has its own. The contract only mints, so the
+/// leaving staker must have approved it on the pass
+fn hand_over_access_pass(storage: &mut dyn Storage, id: &str, from: &Addr, to: &Addr) -> StdResult<Vec<SubMsg>> {
+    let token_id = match ACCESS_PASSES.may_load(storage, (id, from))? {
+        Some(token_id) => token_id,
+        None => return Ok(vec![]),
+    };
+    ACCESS_PASSES.remove(storage, (id, from));
+    let nft_contract = match ACCESS_PASS_NFT.may_load(storage)? {
+        Some(nft_contract) => nft_contract,
+        None => return Ok(vec![]),
+    };
+
+    let msg = if ACCESS_PASSES.has(storage, (id, to)) {
+        Cw721ExecuteMsg::<AccessPassMetadata>::Burn { token_id }
+    } else {
+        ACCESS_PASSES.save(storage, (id, to), &token_id)?;
+        Cw721ExecuteMsg::TransferNft { recipient: to.into(), token_id }
+    };
+    Ok(vec![SubMsg::new(WasmMsg::Execute {
+        contract_addr: nft_contract.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    })])
+}
+
+pub fn execute_approve (
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.closed {
+        Err(ContractError::WorkClosed {})
+    } else if escrow.state == 0 || !escrow.is_expired(&env) {
+        Err(ContractError::NotStarted {})
+    } else if escrow.state == 1 && info.sender != escrow.client {
+        Err(ContractError::NotClient {})
+    } else if escrow.state == 2 && !is_manager(deps.as_ref(), &info.sender)? {
+        Err(ContractError::NotManager {})
+    } else if escrow.state == 3 {
+        Err(ContractError::NotLeft {})
+
+    } else {
+        if escrow.state == 1 {
+            check_deliverable(deps.as_ref(), &env, &escrow)?;
+            // the stakes stop earning emissions once the work settles
+            for (staker, amount) in GenericAccount::parse(&escrow.account_info)?.totals() {
+                change_emission_stake(deps.storage, &env, &escrow.stake_token, &staker, amount, false)?;
+            }
+            // positions are final now, open listings could never be bought
+            drop_listings(deps.storage, &id, None)?;
+            // First, client must approve. Everything but the manager's share is paid now
+            for (_, recipient, amount) in stake_payouts(&escrow)? {
+                let payout = Balance::Cw20(Cw20CoinVerified {
+                    address: escrow.stake_token.clone(),
+                    amount
+                });
+                escrow.balance.try_sub(payout.clone())?;
+                let mut balance = GenericBalance::default();
+                balance.try_add(payout)?;
+                add_claimable(deps.storage, &recipient, &balance)?;
+            }
+            let pool = std::mem::take(&mut escrow.reward_pool);
+            let weights = reward_weights(&escrow, env.block.time.seconds())?;
+            if weights.is_empty() {
+                // nobody to reward, the funds should not stay behind
+                add_claimable(deps.storage, &escrow.client, &pool)?;
+            } else {
+                for (staker, share) in split_pro_rata(&pool, &weights)? {
+                    add_claimable(deps.storage, &staker, &share)?;
+                }
+            }
+            // the work is delivered, the bond goes back
+            let bond = std::mem::take(&mut escrow.client_bond);
+            if !bond.is_empty() {
+                add_claimable(deps.storage, &info.sender, &bond)?;
+            }
+        } else if escrow.state == 2 {
+            //credit all left tokens to manager
+            let balance_manager = std::mem::take(&mut escrow.balance);
+            let payee = manager_payee(deps.as_ref())?.unwrap_or_else(|| info.sender.clone());
+            add_claimable(deps.storage, &payee, &balance_manager)?;
+        }
+ 
+        escrow.state += 1;
+
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+        
+        Ok(Response::new()
+            .add_attribute("action", "approve")
+            .add_attribute("id", id)
+            .add_attribute("to", info.sender))
+    }
+}
+
+/// Client, extra and staker shares of the stake. The manager gets whatever
+/// is left once these are paid, which includes the rounding dust
+fn stake_payouts(escrow: &Escrow) -> StdResult<Vec<(&'static str, Addr, Uint128)>> {
+    let pool = escrow.staked();
+    let split = &escrow.split;
+
+    let client_pool = pool.multiply_ratio(split.client_bps, TOTAL_BPS);
+    let mut payouts = vec![];
+    if escrow.collaborators.is_empty() {
+        payouts.push(("client", escrow.client.clone(), client_pool));
+    } else {
+        let weights: Vec<(Addr, Uint128)> = escrow
+            .collaborators
+            .iter()
+            .map(|c| (c.payout.clone(), Uint128::from(c.weight)))
+            .collect();
+        for ((payout, _), amount) in weights.iter().zip(split_amount(client_pool, &weights)?) {
+            payouts.push(("collaborator", payout.clone(), amount));
+        }
+    }
+    for share in split.extra_shares.iter() {
+        payouts.push(("extra", share.address.clone(), pool.multiply_ratio(share.bps, TOTAL_BPS)));
+    }
+    let stakers = GenericAccount::parse(&escrow.account_info)?.totals();
+    let stakers_pool = pool.multiply_ratio(split.stakers_bps, TOTAL_BPS);
+    for ((staker, _), amount) in stakers.iter().zip(split_amount(stakers_pool, &stakers)?) {
+        payouts.push(("staker", staker.clone(), amount));
+    }
+    payouts.retain(|(_, _, amount)| !amount.is_zero());
+    Ok(payouts)
+}
+
+pub fn execute_set_payout_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+
+    let payout = deps.api.addr_validate(&address)?;
+    let collaborator = escrow
+        .collaborators
+        .iter_mut()
+        .find(|c| c.member == info.sender)
+        .ok_or(ContractError::NotCollaborator {})?;
+    collaborator.payout = payout.clone();
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_payout_address")
+        .add_attribute("id", id)
+        .add_attribute("payout", payout))
+}
+
+fn constant_u64(deps: Deps, key: &str) -> StdResult<u64> {
+    Ok(CONSTANT
+        .may_load(deps.storage, key)?
+        .unwrap_or_default()
+        .parse()
+        .unwrap_or_default())
+}
+
+/// Stake currently held by the stakers who flagged the deliverable
+fn flagged_stake(escrow: &Escrow) -> StdResult<Uint128> {
+    Ok(GenericAccount::parse(&escrow.account_info)?
+        .totals()
+        .into_iter()
+        .filter(|(staker, _)| escrow.flagged_by.contains(staker))
+        .map(|(_, amount)| amount)
+        .sum())
+}
+
+/// The client approval needs a deliverable whose review window is over
+/// and which is not flagged by the threshold share of the stake
+fn check_deliverable(deps: Deps, env: &Env, escrow: &Escrow) -> Result<(), ContractError> {
+    let deliverable = escrow.deliverable.as_ref().ok_or(ContractError::NoDeliverable {})?;
+    if env.block.time.seconds() < deliverable.review_deadline {
+        return Err(ContractError::ReviewWindowOpen {});
+    }
+
+    let threshold_bps = constant_u64(deps, "flag_threshold_bps")?;
+    let staked = escrow.staked();
+    if threshold_bps > 0
+        && !staked.is_zero()
+        && flagged_stake(escrow)?.multiply_ratio(TOTAL_BPS, 1u128) >= staked.multiply_ratio(threshold_bps, 1u128)
+    {
+        return Err(ContractError::DeliverableFlagged {});
+    }
+    Ok(())
+}
+
+pub fn execute_submit_deliverable(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    url: String,
+    content_hash: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.client != info.sender {
+        return Err(ContractError::NotClient {});
+    }
+    if escrow.state == 0 {
+        return Err(ContractError::NotStarted {});
+    }
+    if escrow.state > 1 {
+        return Err(ContractError::AlreadySettled {});
+    }
+    if escrow.closed {
+        return Err(ContractError::WorkClosed {});
+    }
+
+    let now = env.block.time.seconds();
+    let review_deadline = now
+        .checked_add(constant_u64(deps.as_ref(), "review_window")?)
+        .ok_or(ContractError::InvalidDeadline {})?;
+    escrow.deliverable = Some(Deliverable {
+        url,
+        content_hash,
+        submitted_at: now,
+        review_deadline,
+    });
+    escrow.flagged_by = vec![];
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "submit_deliverable")
+        .add_attribute("id", id))
+}
+
+pub fn execute_flag_deliverable(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.state > 1 {
+        return Err(ContractError::AlreadySettled {});
+    }
+    let deliverable = escrow.deliverable.as_ref().ok_or(ContractError::NoDeliverable {})?;
+    if env.block.time.seconds() >= deliverable.review_deadline {
+        return Err(ContractError::ReviewWindowClosed {});
+    }
+    let is_staker = GenericAccount::parse(&escrow.account_info)?
+        .account
+        .iter()
+        .any(|account| account.addr == info.sender);
+    if !is_staker {
+        return Err(ContractError::DidntStaked {});
+    }
+    if escrow.flagged_by.contains(&info.sender) {
+        return Err(ContractError::AlreadyFlagged {});
+    }
+
+    escrow.flagged_by.push(info.sender.clone());
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "flag_deliverable")
+        .add_attribute("id", id)
+        .add_attribute("staker", info.sender))
+}
+
+pub fn execute_slash_bond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+
+    if escrow.arbiter.as_ref() != Some(&info.sender) {
+        check_configured_manager(deps.as_ref(), &info.sender)?;
+    }
+    if escrow.state > 1 {
+        return Err(ContractError::AlreadySettled {});
+    }
+    if escrow.closed {
+        return Err(ContractError::WorkClosed {});
+    }
+    // only a funded work left without a deliverable past the timeout
+    let funded_at = escrow.funded_at.ok_or(ContractError::NotStarted {})?;
+    let deadline = funded_at
+        .checked_add(constant_u64(deps.as_ref(), "delivery_timeout")?)
+        .ok_or(ContractError::DeliveryNotMissed {})?;
+    if escrow.deliverable.is_some() || env.block.time.seconds() < deadline {
+        return Err(ContractError::DeliveryNotMissed {});
+    }
+    if escrow.client_bond.is_empty() {
+        return Err(ContractError::NoBond {});
+    }
+    let stakers = GenericAccount::parse(&escrow.account_info)?.totals();
+    if stakers.is_empty() {
+        return Err(ContractError::NobodyStaked {});
+    }
+
+    // the stakers keep the rewards they were promised for the delivery
+    let mut slashed = std::mem::take(&mut escrow.client_bond);
+    slashed.try_add_balance(&std::mem::take(&mut escrow.reward_pool))?;
+    for (staker, share) in split_pro_rata(&slashed, &stakers)? {
+        add_claimable(deps.storage, &staker, &share)?;
+    }
+    escrow.closed = true;
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "slash_bond")
+        .add_attribute("id", id))
+}
+
+pub fn execute_withdraw_bond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.client != info.sender {
+        return Err(ContractError::NotClient {});
+    }
+    if escrow.state > 0 {
+        return Err(ContractError::AlreadyStarted {});
+    }
+    if !escrow.is_expired(&env) {
+        return Err(ContractError::WorkNotExpired {});
+    }
+    if escrow.closed {
+        return Err(ContractError::WorkClosed {});
+    }
+
+    let mut balance = std::mem::take(&mut escrow.client_bond);
+    balance.try_add_balance(&std::mem::take(&mut escrow.reward_pool))?;
+    if balance.is_empty() {
+        return Err(ContractError::NoBond {});
+    }
+    let messages = send_tokens_or_claim(deps.storage, &info.sender, &balance)?;
+    // the goal was missed, the stakers can still refund
+    escrow.closed = true;
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "withdraw_bond")
+        .add_attribute("id", id)
+        .add_submessages(messages))
+}
+
+/// Divides every token of the balance by weight. Rounding dust goes to the
+/// last entry so nothing stays behind in the contract
+fn split_pro_rata<W: Into<Uint256> + Copy>(
+    balance: &GenericBalance,
+    weights: &[(Addr, W)],
+) -> Result<Vec<(Addr, GenericBalance)>, ContractError> {
+    let mut shares: Vec<(Addr, GenericBalance)> = weights
+        .iter()
+        .map(|(addr, _)| (addr.clone(), GenericBalance::default()))
+        .collect();
+
+    for coin in balance.native.iter() {
+        for (i, part) in split_amount(coin.amount, weights)?.into_iter().enumerate() {
+            shares[i].1.try_add(Balance::from(vec![Coin {
+                denom: coin.denom.clone(),
+                amount: part,
+            }]))?;
+        }
+    }
+    for token in balance.cw20.iter() {
+        for (i, part) in split_amount(token.amount, weights)?.into_iter().enumerate() {
+            shares[i].1.try_add(Balance::Cw20(Cw20CoinVerified {
+                address: token.address.clone(),
+                amount: part,
+            }))?;
+        }
+    }
+    Ok(shares)
+}
+
+fn split_amount<W: Into<Uint256> + Copy>(amount: Uint128, weights: &[(Addr, W)]) -> StdResult<Vec<Uint128>> {
+    let mut total = Uint256::zero();
+    for (_, weight) in weights.iter() {
+        total = total.checked_add((*weight).into())?;
+    }
+    let mut left = amount;
+    let mut parts: Vec<Uint128> = vec![];
+    for (i, (_, weight)) in weights.iter().enumerate() {
+        let part = if total.is_zero() {
+            Uint128::zero()
+        } else if i + 1 == weights.len() {
+            left
+        } else {
+            // weight <= total, so the part never exceeds amount
+            Uint128::try_from(Uint256::from(amount).multiply_ratio(*weight, total))?
+        };
+        left = left.checked_sub(part)?;
+        parts.push(part);
+    }
+    Ok(parts)
+}
+
+pub fn account_staked(deps:Deps, account_info:&str, addr:Addr) -> (String, String) {
+    let accounts: Vec<&str> = account_info.split(';').collect();
+
+    for account in accounts {
+        let infos:Vec<&str> = account.split(':').collect();
+        
+        if (infos.len() != 4 && infos.len() != 5) || deps.api.addr_validate(infos[0]).unwrap() != addr {
+            continue;
+        }
+        return (String::from(account), String::from(infos[1]));
+    }
+    (String::from(""), String::from(""))
+}
+
+pub fn execute_refund(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+
+    let mut messages: Vec<SubMsg> = vec![];
+    // a closed work can be left any time
+    if !escrow.closed && escrow.state > 0 && escrow.is_expired(&env) {
+        Err(ContractError::AlreadyStarted {})
+    } else if !escrow.closed && !escrow.is_expired(&env) {
+        Err(ContractError::WorkNotExpired {})
+    } else if escrow.account_info.is_empty() {
+        Err(ContractError::NobodyStaked {})
+    } else {
+        let (mine, others): (Vec<_>, Vec<_>) = GenericAccount::parse(&escrow.account_info)?
+            .account
+            .into_iter()
+            .partition(|account| account.addr == info.sender);
+        let exist:bool = !mine.is_empty();
+
+        let mut refund_balance = GenericBalance::default();
+        for account in mine {
+            if env.block.time < Timestamp::from_seconds(account.end_time) &&
+            env.block.time > Timestamp::from_seconds(account.start_time)
+            {
+                return Err(ContractError::AccountNotExpired {});
+            }
+            let stake = Balance::Cw20(Cw20CoinVerified {
+                address: escrow.stake_token.clone(),
+                amount: account.amount
+            });
+            //move tokens from escrow.balance to the refund
+            escrow.balance.try_sub(stake.clone())?;
+            refund_balance.try_add(stake)?;
+        }
+
+        if !exist {
+            return Err(ContractError::DidntStaked {});
+        }
+        for token in refund_balance.cw20.iter() {
+            change_emission_stake(deps.storage, &env, &token.address, &info.sender, token.amount, false)?;
+        }
+
+        let unbonding_period = match escrow.unbonding_period {
+            Some(period) => period,
+            None => CONSTANT
+                .may_load(deps.storage, "unbonding_period")?
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or_default(),
+        };
+        if unbonding_period > 0 {
+            let mut claims = UNBONDING.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+            claims.push(UnbondingClaim {
+                id: id.clone(),
+                balance: refund_balance,
+                release_at: Expiration::AtTime(env.block.time.plus_seconds(unbonding_period)),
+            });
+            UNBONDING.save(deps.storage, &info.sender, &claims)?;
+        } else {
+            messages = send_tokens_or_claim(deps.storage, &info.sender, &refund_balance)?;
+        }
+
+        escrow.account_info = GenericAccount { account: others }.to_account_info();
+        drop_listings(deps.storage, &id, Some(&info.sender))?;
+        clear_encrypted_urls(deps.storage, &id, Some(&info.sender))?;
+        // nobody joins a closed work, it just empties out
+        if !escrow.closed {
+            promote_waitlist(deps.branch(), &env, &id, &mut escrow)?;
+            if escrow.staked() >= escrow.stake_amount /*&& escrow.is_expired(&env)*/ {
+                escrow.state = 1; //set to started state
+                escrow.funded_at.get_or_insert(env.block.time.seconds());
+                messages.append(&mut mint_access_passes(deps.storage, &id, &escrow)?);
+            } else {
+                escrow.state = 0;
+                escrow.funded_at = None;
+            }
+        }
+
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+        Ok(Response::new()
+        .add_attribute("action", "refund")
+        .add_attribute("id", id)
+        .add_attribute("to", info.sender)
+        .add_submessages(messages))
+    }
+}
+
+
+pub fn execute_transfer_position(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.state > 1 {
+        return Err(ContractError::NotTransferable {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    if amount.is_zero() || recipient == info.sender {
+        return Err(ContractError::InvalidTransfer {});
+    }
+
+    let mut accounts = GenericAccount::parse(&escrow.account_info)?;
+    let owned = accounts
+        .totals()
+        .into_iter()
+        .find(|(addr, _)| *addr == info.sender)
+        .map(|(_, owned)| owned)
+        .unwrap_or_default();
+    let listed = listed_stake(deps.storage, &id, &info.sender)?;
+    if owned >= amount && owned - amount < listed {
+        return Err(ContractError::StakeListed {});
+    }
+    check_recipient(deps.as_ref(), &id, &escrow, &info.sender, &recipient, amount)?;
+    accounts.transfer(&info.sender, &recipient, amount)?;
+    escrow.account_info = accounts.to_account_info();
+    check_capacity(&escrow)?;
+    // a partial transfer leaves the sender their pass and mints one to the recipient
+    let mut pass_msgs = vec![];
+    if owned == amount {
+        clear_encrypted_urls(deps.storage, &id, Some(&info.sender))?;
+        pass_msgs = hand_over_access_pass(deps.storage, &id, &info.sender, &recipient)?;
+    }
+
+    change_emission_stake(deps.storage, &env, &escrow.stake_token, &info.sender, amount, false)?;
+    change_emission_stake(deps.storage, &env, &escrow.stake_token, &recipient, amount, true)?;
+    promote_waitlist(deps.branch(), &env, &id, &mut escrow)?;
+
+    if escrow.state == 1 {
+        pass_msgs.append(&mut mint_access_passes(deps.storage, &id, &escrow)?);
+    }
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_position")
+        .add_attribute("id", id)
+        .add_attribute("from", info.sender)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount)
+        .add_submessages(pass_msgs))
+}
+
+/// Stake of seller held back by their open listings of id
+fn listed_stake(storage: &dyn Storage, id: &str, seller: &Addr) -> StdResult<Uint128> {
+    let mut listed = Uint128::zero();
+    for listing_id in SELLER_LISTINGS.may_load(storage, (id, seller))?.unwrap_or_default() {
+        listed = listed.checked_add(LISTINGS.load(storage, listing_id)?.amount)?;
+    }
+    Ok(listed)
+}
+
+/// Forgets one listing, releasing its stake
+fn remove_listing(storage: &mut dyn Storage, listing_id: u64, listing: &Listing) -> StdResult<()> {
+    LISTINGS.remove(storage, listing_id);
+    let key = (listing.id.as_str(), &listing.seller);
+    let mut open = SELLER_LISTINGS.may_load(storage, key)?.unwrap_or_default();
+    open.retain(|open_id| *open_id != listing_id);
+    if open.is_empty() {
+        SELLER_LISTINGS.remove(storage, key);
+    } else {
+        SELLER_LISTINGS.save(storage, key, &open)?;
+    }
+    Ok(())
+}
+
+/// Drops every open listing of seller for id, or of everyone when seller is None
+fn drop_listings(storage: &mut dyn Storage, id: &str, seller: Option<&Addr>) -> StdResult<()> {
+    let sellers = match seller {
+        Some(seller) => vec![seller.clone()],
+        None => SELLER_LISTINGS
+            .prefix(id)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+    for seller in sellers {
+        for listing_id in SELLER_LISTINGS.may_load(storage, (id, &seller))?.unwrap_or_default() {
+            LISTINGS.remove(storage, listing_id);
+        }
+        SELLER_LISTINGS.remove(storage, (id, &seller));
+    }
+    Ok(())
+}
+
+pub fn execute_list_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    amount: Uint128,
+    price: Uint128,
+    denom: Denom,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    if escrow.state > 1 {
+        return Err(ContractError::NotTransferable {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::InvalidTransfer {});
+    }
+    if price.is_zero() {
+        return Err(ContractError::InvalidPrice {});
+    }
+    let owned = GenericAccount::parse(&escrow.account_info)?
+        .totals()
+        .into_iter()
+        .find(|(addr, _)| *addr == info.sender)
+        .map(|(_, amount)| amount)
+        .ok_or(ContractError::DidntStaked {})?;
+    if owned < amount {
+        return Err(ContractError::InsufficientBalance {});
+    }
+    // the same stake can only be on one listing at a time
+    if owned < listed_stake(deps.storage, &id, &info.sender)?.checked_add(amount).map_err(StdError::from)? {
+        return Err(ContractError::StakeListed {});
+    }
+
+    let denom = match denom {
+        Denom::Native(denom) => {
+            if !MARKET_DENOMS.has(deps.storage, &denom) {
+                return Err(ContractError::DenomNotAccepted {});
+            }
+            Denom::Native(denom)
+        }
+        Denom::Cw20(address) => {
+            let address = deps.api.addr_validate(address.as_str())?;
+            check_stake_token(deps.as_ref(), &address)?;
+            Denom::Cw20(address)
+        }
+    };
+
+    let listing_id = LISTING_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    LISTING_SEQ.save(deps.storage, &listing_id)?;
+    LISTINGS.save(deps.storage, listing_id, &Listing {
+        id: id.clone(),
+        seller: info.sender.clone(),
+        amount,
+        price,
+        denom,
+    })?;
+    SELLER_LISTINGS.update(deps.storage, (&id, &info.sender), |open| -> StdResult<_> {
+        let mut open = open.unwrap_or_default();
+        open.push(listing_id);
+        Ok(open)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "list_position")
+        .add_attribute("id", id)
+        .add_attribute("listing_id", listing_id.to_string()))
+}
+
+pub fn execute_cancel_listing(
+    deps: DepsMut,
+    info: MessageInfo,
+    listing_id: u64,
+) -> Result<Response, ContractError> {
+    let listing = LISTINGS.load(deps.storage, listing_id)?;
+    if listing.seller != info.sender {
+        return Err(ContractError::NotSeller {});
+    }
+    remove_listing(deps.storage, listing_id, &listing)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_listing")
+        .add_attribute("listing_id", listing_id.to_string()))
+}
+
+/// Moves the listed stake to the buyer and pays the seller,
+/// minus the royalty for the client of the work
+pub fn execute_buy_position(
+    mut deps: DepsMut,
+    env: Env,
+    listing_id: u64,
+    balance: Balance,
+    buyer: &Addr,
+) -> Result<Response, ContractError> {
+    let listing = LISTINGS.load(deps.storage, listing_id)?;
+    if listing.seller == *buyer {
+        return Err(ContractError::InvalidTransfer {});
+    }
+    let paid = match (&listing.denom, &balance) {
+        (Denom::Native(denom), Balance::Native(coins)) => {
+            coins.0.len() == 1 && coins.0[0].denom == *denom && coins.0[0].amount == listing.price
+        }
+        (Denom::Cw20(address), Balance::Cw20(token)) => {
+            token.address == *address && token.amount == listing.price
+        }
+        _ => false,
+    };
+    if !paid {
+        return Err(ContractError::WrongPayment {});
+    }
+
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &listing.id)?;
+    if escrow.state > 1 {
+        return Err(ContractError::NotTransferable {});
+    }
+    check_recipient(deps.as_ref(), &listing.id, &escrow, &listing.seller, buyer, listing.amount)?;
+    let mut accounts = GenericAccount::parse(&escrow.account_info)?;
+    accounts.transfer(&listing.seller, buyer, listing.amount)?;
+    escrow.account_info = accounts.to_account_info();
+    check_capacity(&escrow)?;
+    let mut messages: Vec<SubMsg> = vec![];
+    if !accounts.account.iter().any(|account| account.addr == listing.seller) {
+        clear_encrypted_urls(deps.storage, &listing.id, Some(&listing.seller))?;
+        messages = hand_over_access_pass(deps.storage, &listing.id, &listing.seller, buyer)?;
+    }
+
+    change_emission_stake(deps.storage, &env, &escrow.stake_token, &listing.seller, listing.amount, false)?;
+    change_emission_stake(deps.storage, &env, &escrow.stake_token, buyer, listing.amount, true)?;
+    promote_waitlist(deps.branch(), &env, &listing.id, &mut escrow)?;
+
+    let royalty_bps: u16 = CONSTANT
+        .may_load(deps.storage, "royalty_bps")?
+        .unwrap_or_default()
+        .parse()
+        .unwrap_or_default();
+    let royalty = listing.price.multiply_ratio(royalty_bps, TOTAL_BPS);
+    let proceeds = listing.price.checked_sub(royalty).map_err(StdError::from)?;
+
+    for (to, amount) in [(&escrow.client, royalty), (&listing.seller, proceeds)] {
+        if !amount.is_zero() {
+            messages.append(&mut send_tokens_or_claim(deps.storage, to, &denom_balance(&listing.denom, amount))?);
+        }
+    }
+    if escrow.state == 1 {
+        messages.append(&mut mint_access_passes(deps.storage, &listing.id, &escrow)?);
+    }
+
+    ESCROWS.save(deps.storage, &listing.id, &escrow)?;
+    remove_listing(deps.storage, listing_id, &listing)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "buy_position")
+        .add_attribute("id", listing.id)
+        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("seller", listing.seller)
+        .add_attribute("buyer", buyer.clone())
+        .add_submessages(messages))
+}
+
+pub fn execute_remove(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    // a closed work is done once every staker refunded
+    let finished = escrow.state == 3 || (escrow.closed && escrow.account_info.is_empty());
+    if !finished {
+        Err(ContractError::NotFinished {})
+    } else if !is_manager(deps.as_ref(), &info.sender)? {
+        Err(ContractError::NotManager {})
+    } else {
+        // deposits still waiting for a slot go back before the escrow is gone
+        let mut messages: Vec<SubMsg> = vec![];
+        for entry in WAITLISTS.may_load(deps.storage, &id)?.unwrap_or_default() {
+            let mut refund_balance = GenericBalance::default();
+            refund_balance.try_add(Balance::Cw20(Cw20CoinVerified {
+                address: escrow.stake_token.clone(),
+                amount: entry.amount,
+            }))?;
+            messages.append(&mut send_tokens_or_claim(deps.storage, &entry.addr, &refund_balance)?);
+        }
+
+        // we delete the escrow
+        ESCROWS.remove(deps.storage, &id);
+        WORK_HISTORY.remove(deps.storage, &id);
+        WAITLISTS.remove(deps.storage, &id);
+        drop_listings(deps.storage, &id, None)?;
+        clear_encrypted_urls(deps.storage, &id, None)?;
+        // the minted passes stay with their owners, only the lookup goes
+        let passes = ACCESS_PASSES
+            .prefix(&id)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for staker in passes {
+            ACCESS_PASSES.remove(deps.storage, (&id, &staker));
+        }
+
+        Ok(Response::new()
+        .add_attribute("action", "remove")
+        .add_attribute("id", id)
+        .add_submessages(messages))
+    }
+}
+
+/// Lowercases a hex encoded sha256, failing on anything else
+fn check_hash(hash: &str) -> Result<String, ContractError> {
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidHash {});
+    }
+    Ok(hash.to_ascii_lowercase())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(data: &str) -> String {
+    to_hex(&Sha256::digest(data.as_bytes()))
+}
+
+fn from_hex32(hash: &str) -> Result<[u8; 32], ContractError> {
+    let hash = check_hash(hash)?;
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16).map_err(|_| ContractError::InvalidHash {})?;
+    }
+    Ok(bytes)
+}
+
+/// Walks the proof from the sha256 of the address up to the root
+fn verify_merkle_proof(root: &str, address: &Addr, proof: &[String]) -> Result<bool, ContractError> {
+    let mut hash: [u8; 32] = Sha256::digest(address.as_bytes()).into();
+    for sibling in proof {
+        let sibling = from_hex32(sibling)?;
+        let (first, second) = if hash <= sibling { (hash, sibling) } else { (sibling, hash) };
+        hash = Sha256::digest(&[first, second].concat()).into();
+    }
+    Ok(to_hex(&hash) == root)
+}
+
+fn check_allowlist(
+    deps: Deps,
+    id: &str,
+    escrow: &Escrow,
+    sender: &Addr,
+    proof: Option<&[String]>,
+) -> Result<(), ContractError> {
+    let allowed = match &escrow.allowlist {
+        None => true,
+        Some(Allowlist::Addresses) => ALLOWLISTS.has(deps.storage, (id, sender)),
+        Some(Allowlist::MerkleRoot(root)) => match proof {
+            Some(proof) => verify_merkle_proof(root, sender, proof)?,
+            None => false,
+        },
+    };
+    if !allowed {
+        return Err(ContractError::NotAllowlisted {});
+    }
+    Ok(())
+}
+
+/// Moves waiting deposits into positions, in order, as far as the caps and the group gate allow
+fn promote_waitlist(
+    deps: DepsMut,
+    env: &Env,
+    id: &str,
+    escrow: &mut Escrow,
+) -> Result<(), ContractError> {
+    let waitlist = match WAITLISTS.may_load(deps.storage, id)? {
+        Some(waitlist) => waitlist,
+        None => return Ok(()),
+    };
+
+    let mut waiting: Vec<WaitlistEntry> = vec![];
+    for entry in waitlist {
+        // group weights may have changed, or the staker got more stake meanwhile
+        if check_group(deps.as_ref(), escrow, &entry.addr, entry.amount).is_err() {
+            waiting.push(entry);
+            continue;
+        }
+        let account = format!(
+            ";{}:{}:{}:{}:{}",
+            entry.addr, entry.amount, entry.start_time, entry.end_time, env.block.time.seconds()
+        );
+        let before = escrow.account_info.clone();
+        escrow.account_info.push_str(&account);
+        if check_capacity(escrow).is_err() {
+            escrow.account_info = before;
+            waiting.push(entry);
+            continue;
+        }
+        escrow.balance.try_add(Balance::Cw20(Cw20CoinVerified {
+            address: escrow.stake_token.clone(),
+            amount: entry.amount,
+        }))?;
+        change_emission_stake(deps.storage, env, &escrow.stake_token, &entry.addr, entry.amount, true)?;
+    }
+
+    if waiting.is_empty() {
+        WAITLISTS.remove(deps.storage, id);
+    } else {
+        WAITLISTS.save(deps.storage, id, &waiting)?;
+    }
+    Ok(())
+}
+
+pub fn execute_leave_waitlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    let (mine, others): (Vec<_>, Vec<_>) = WAITLISTS
+        .may_load(deps.storage, &id)?
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|entry| entry.addr == info.sender);
+    if mine.is_empty() {
+        return Err(ContractError::NotWaitlisted {});
+    }
+
+    let stake_token = ESCROWS.load(deps.storage, &id)?.stake_token;
+    let mut refund_balance = GenericBalance::default();
+    for entry in mine {
+        refund_balance.try_add(Balance::Cw20(Cw20CoinVerified {
+            address: stake_token.clone(),
+            amount: entry.amount,
+        }))?;
+    }
+    if others.is_empty() {
+        WAITLISTS.remove(deps.storage, &id);
+    } else {
+        WAITLISTS.save(deps.storage, &id, &others)?;
+    }
+    let messages = send_tokens_or_claim(deps.storage, &info.sender, &refund_balance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "leave_waitlist")
+        .add_attribute("id", id)
+        .add_attribute("to", info.sender)
+        .add_submessages(messages))
+}
+
+/// Checks the positions after a change against the work's caps
+fn check_capacity(escrow: &Escrow) -> Result<(), ContractError> {
+    let totals = GenericAccount::parse(&escrow.account_info)?.totals();
+
+    if let Some(max_stakers) = escrow.max_stakers {
+        if totals.len() > max_stakers as usize {
+            return Err(ContractError::TooManyStakers {});
+        }
+    }
+    if let Some(max_stake) = escrow.max_stake_per_address {
+        if totals.iter().any(|(_, amount)| *amount > max_stake) {
+            return Err(ContractError::StakeCapExceeded {});
+        }
+    }
+    Ok(())
+}
+
+/// Members only, and within the weighted cap if the work sets one
+fn check_group(deps: Deps, escrow: &Escrow, sender: &Addr, amount: Uint128) -> Result<(), ContractError> {
+    let group = match &escrow.group {
+        Some(group) => group,
+        None => return Ok(()),
+    };
+    let weight = Cw4Contract::new(group.address.clone())
+        .is_member(&deps.querier, sender, None)?
+        .ok_or(ContractError::NotGroupMember {})?;
+
+    if let Some(stake_per_weight) = group.stake_per_weight {
+        let mut staked = amount;
+        for account in GenericAccount::parse(&escrow.account_info)?.account {
+            if account.addr == *sender {
+                staked = staked.checked_add(account.amount).map_err(StdError::from)?;
+            }
+        }
+        let cap = stake_per_weight.checked_mul(Uint128::from(weight)).map_err(StdError::from)?;
+        if staked > cap {
+            return Err(ContractError::GroupCapExceeded {});
+        }
+    }
+    Ok(())
+}
+
+/// Runs whoever receives stake through the gates of a top up. Merkle allowlists
+/// need a proof, so positions in those works only move to stakers already in
+fn check_recipient(
+    deps: Deps,
+    id: &str,
+    escrow: &Escrow,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let totals = GenericAccount::parse(&escrow.account_info)?.totals();
+    if totals.iter().any(|(staker, _)| staker == to) {
+        return check_group(deps, escrow, to, amount);
+    }
+    check_allowlist(deps, id, escrow, to, None)?;
+    check_group(deps, escrow, to, amount)?;
+    if let Some(pricing) = &escrow.pricing {
+        // a sender moving out frees its slot for the recipient
+        let slots = totals
+            .iter()
+            .filter(|(staker, owned)| !(staker == from && *owned == amount))
+            .count();
+        if amount < pricing.price(slots as u32)? {
+            return Err(ContractError::InsufficientTopUp {});
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the stored addresses of id, returning the allowlist for the escrow
+fn save_allowlist(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    id: &str,
+    allowlist: Option<AllowlistMsg>,
+) -> Result<Option<Allowlist>, ContractError> {
+    let previous = ALLOWLISTS
+        .prefix(id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for address in previous {
+        ALLOWLISTS.remove(storage, (id, &address));
+    }
+
+    Ok(match allowlist {
+        Some(AllowlistMsg::Addresses(addresses)) => {
+            for address in addresses {
+                ALLOWLISTS.save(storage, (id, &api.addr_validate(&address)?), &true)?;
+            }
+            Some(Allowlist::Addresses)
+        }
+        Some(AllowlistMsg::MerkleRoot(root)) => Some(Allowlist::MerkleRoot(check_hash(&root)?)),
+        None => None,
+    })
+}
+
+pub fn execute_set_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    allowlist: Option<AllowlistMsg>,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if info.sender != escrow.client {
+        return Err(ContractError::NotClient {});
+    }
+
+    escrow.allowlist = save_allowlist(deps.storage, deps.api, &id, allowlist)?;
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_allowlist")
+        .add_attribute("id", id))
+}
+
+pub fn execute_update_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    if info.sender != escrow.client {
+        return Err(ContractError::NotClient {});
+    }
+    if escrow.allowlist != Some(Allowlist::Addresses) {
+        return Err(ContractError::NoAddressAllowlist {});
+    }
+
+    for address in add {
+        ALLOWLISTS.save(deps.storage, (&id, &deps.api.addr_validate(&address)?), &true)?;
+    }
+    for address in remove {
+        ALLOWLISTS.remove(deps.storage, (&id, &deps.api.addr_validate(&address)?));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_allowlist")
+        .add_attribute("id", id))
+}
+
+pub fn execute_reveal_work_url(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    work_url: String,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if info.sender != escrow.client {
+        return Err(ContractError::NotClient {});
+    }
+    if escrow.state == 0 {
+        return Err(ContractError::NotStarted {});
+    }
+    if !escrow.work_url.is_empty() {
+        return Err(ContractError::AlreadyRevealed {});
+    }
+    if sha256_hex(&work_url) != escrow.work_url_hash {
+        return Err(ContractError::HashMismatch {});
+    }
 
-    if escrow.is_expired(&env) && escrow.state > 0 {
-        return Err(ContractError::StakeFinished {});
-    }
+    escrow.work_url = work_url;
+    ESCROWS.save(deps.storage, &id, &escrow)?;
 
+    Ok(Response::new()
+        .add_attribute("action", "reveal_work_url")
+        .add_attribute("id", id))
+}
 
-    let mut cwval:u128 = 0;
-    if let Balance::Cw20(token) = &balance {
-        // ensure the token is on the whitelist
-        if !escrow.cw20_whitelist.iter().any(|t| t == &token.address) {
-            return Err(ContractError::NotInWhitelist {});
-        } else {
-           cwval = token.amount.u128();
+/// Forgets the key and encrypted url of a staker, or of every staker when None
+fn clear_encrypted_urls(storage: &mut dyn Storage, id: &str, staker: Option<&Addr>) -> StdResult<()> {
+    let stakers = match staker {
+        Some(staker) => vec![staker.clone()],
+        None => {
+            let mut stakers = STAKER_PUBKEYS
+                .prefix(id)
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            stakers.extend(
+                ENCRYPTED_URLS
+                    .prefix(id)
+                    .keys(storage, None, None, Order::Ascending)
+                    .collect::<StdResult<Vec<_>>>()?,
+            );
+            stakers
         }
     };
-    
-    // let account_info:AccountInfo = AccountInfo {
-    //     addr: sender.clone(),
-    //     amount: cwval,
-    //     start_time: msg.start_time,
-    //     end_time: msg.end_time
-    // };
-
-    let str:String = escrow.account_info + ";" + 
-    sender.to_string().as_str() + ":" +
-    cwval.to_string().as_str() + ":" +
-    msg.start_time.to_string().as_str() + ":" +
-    msg.end_time.to_string().as_str();
-    escrow.account_info = str;
-    // escrow.account_info.add_account(account_info);
-    escrow.balance.add_tokens(balance);
-    
-    if escrow.balance.cw20.get(0).unwrap().amount >= Uint128::from(escrow.stake_amount) /*&& escrow.is_expired(&env)*/ {
-        escrow.state = 1; //set to started state
+    for staker in stakers {
+        STAKER_PUBKEYS.remove(storage, (id, &staker));
+        ENCRYPTED_URLS.remove(storage, (id, &staker));
     }
-    // and save
-    ESCROWS.save(deps.storage, &msg.id, &escrow)?;
-    //return Err(ContractError::NotInWhitelist {});
-    let res = Response::new().add_attributes(vec![("action", "top_up"), ("id", msg.id.as_str())]);
-    Ok(res)
+    Ok(())
 }
 
-pub fn execute_approve (
+pub fn execute_register_pubkey(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
     id: String,
+    pubkey: Binary,
 ) -> Result<Response, ContractError> {
     // this fails is no escrow there
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    let is_staker = GenericAccount::parse(&escrow.account_info)?
+        .account
+        .iter()
+        .any(|account| account.addr == info.sender);
+    if !is_staker {
+        return Err(ContractError::DidntStaked {});
+    }
+    let valid = match pubkey.len() {
+        33 => pubkey[0] == 0x02 || pubkey[0] == 0x03,
+        65 => pubkey[0] == 0x04,
+        _ => false,
+    };
+    if !valid {
+        return Err(ContractError::InvalidPubkey {});
+    }
 
-    let mut escrow = ESCROWS.load(deps.storage, &id)?;
-    let manager_addr:String = CONSTANT.load(deps.storage, "manager_addr")?;
-    let maddr:Addr = deps.api.addr_validate(&manager_addr)?;
+    STAKER_PUBKEYS.save(deps.storage, (&id, &info.sender), &pubkey)?;
 
-    if escrow.state == 0 || !escrow.is_expired(&env) {
-        Err(ContractError::NotStarted {})
-    } else if escrow.state == 1 && info.sender != escrow.client {
-        Err(ContractError::NotClient {})
-    } else if escrow.state == 2 && info.sender != maddr {
-        Err(ContractError::NotManager {})
-    } else if escrow.state == 3 {
-        Err(ContractError::NotLeft {})
+    Ok(Response::new()
+        .add_attribute("action", "register_pubkey")
+        .add_attribute("id", id)
+        .add_attribute("staker", info.sender))
+}
 
-    } else {
-        let addr:Addr = escrow.balance.cw20.get(0).unwrap().address.clone();
-        let mut messages: Vec<SubMsg> = vec![];
-        if escrow.state == 1 {
-            // First, client must approve
-            let rate_manager:Uint128 = CONSTANT.load(deps.storage, "rate_manager")?.parse().unwrap();
-            let rate_client:Uint128 = Uint128::from(100u128).checked_sub(rate_manager).unwrap();
-            let client_amount:Uint128 = Uint128::from(escrow.stake_amount).checked_mul(rate_client).unwrap().checked_div(Uint128::from(100u128)).unwrap();
-            
-            let token_client = Cw20CoinVerified {
-                address: addr.clone(),
-                amount: client_amount
-            };
-            
-            let balance_client = GenericBalance {
-                native: vec![],
-                cw20: vec![token_client],
-            };
+pub fn execute_upload_encrypted_urls(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    blobs: Vec<EncryptedUrlMsg>,
+) -> Result<Response, ContractError> {
+    // this fails is no escrow there
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    if info.sender != escrow.client {
+        return Err(ContractError::NotClient {});
+    }
+    if escrow.state == 0 {
+        return Err(ContractError::NotStarted {});
+    }
 
-            messages = send_tokens(&info.sender, &balance_client)?;
-            escrow.balance.sub_tokens(Balance::Cw20(Cw20CoinVerified {
-                address: addr.clone(),
-                amount: client_amount
-            }));
-        } else if escrow.state == 2 {
-            //send all left tokens to manager
-            
-            messages = send_tokens(&info.sender, &escrow.balance)?;
-            escrow.balance.sub_tokens(Balance::Cw20(Cw20CoinVerified {
-                address: addr.clone(),
-                amount: escrow.balance.cw20.get(0).unwrap().amount
-            }));
+    let stakers: Vec<Addr> = GenericAccount::parse(&escrow.account_info)?
+        .totals()
+        .into_iter()
+        .map(|(staker, _)| staker)
+        .collect();
+    let mut uploads: Vec<(Addr, Binary)> = vec![];
+    for blob in blobs {
+        let staker = deps.api.addr_validate(&blob.staker)?;
+        if !stakers.contains(&staker) {
+            return Err(ContractError::NotStaker { address: blob.staker });
         }
- 
-        escrow.state = escrow.state + 1;
-
-        ESCROWS.save(deps.storage, &id, &escrow)?;
-        
-        Ok(Response::new()
-            .add_attribute("action", "approve")
-            .add_attribute("id", id)
-            .add_attribute("to", info.sender)
-            .add_submessages(messages))
+        uploads.push((staker, blob.ciphertext));
     }
-}
-
-pub fn accountStaked(deps:Deps, account_info:&String, addr:Addr) -> (String, String) {
-    let accounts: Vec<&str> = account_info.split(';').collect();
-
-    for account in accounts {
-        let infos:Vec<&str> = account.split(':').collect();
-        
-        if infos.len() != 4 || deps.api.addr_validate(&infos[0]).unwrap() != addr {
-            continue;
+    // every staker needs a blob, so the upload waits until all of them registered a key
+    for staker in stakers.iter() {
+        if !STAKER_PUBKEYS.has(deps.storage, (&id, staker)) {
+            return Err(ContractError::NoPubkey { staker: staker.to_string() });
+        }
+        if !uploads.iter().any(|(addr, _)| addr == staker) {
+            return Err(ContractError::MissingBlob { staker: staker.to_string() });
         }
-        return (String::from(account), String::from(infos[1]));
     }
-    (String::from(""), String::from(""))
+
+    // blobs of former stakers go away with the old upload
+    let previous = ENCRYPTED_URLS
+        .prefix(&id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for staker in previous {
+        ENCRYPTED_URLS.remove(deps.storage, (&id, &staker));
+    }
+    for (staker, ciphertext) in uploads {
+        ENCRYPTED_URLS.save(deps.storage, (&id, &staker), &ciphertext)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "upload_encrypted_urls")
+        .add_attribute("id", id))
 }
 
-pub fn execute_refund(
+pub fn execute_update_work(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    id: String,
+    msg: UpdateWorkMsg,
 ) -> Result<Response, ContractError> {
+    let id = msg.id;
     // this fails is no escrow there
     let mut escrow = ESCROWS.load(deps.storage, &id)?;
 
-    let mut messages: Vec<SubMsg> = vec![];
-    if escrow.state > 0 && escrow.is_expired(&env) {
-        Err(ContractError::AlreadyStarted {})
-    } else if !escrow.is_expired(&env) {
-        Err(ContractError::WorkNotExpired {})
-    } else if escrow.account_info.len() == 0 {
-        Err(ContractError::NobodyStaked {})
-    } else {
-        // we delete the escrow
-        // ESCROWS.remove(deps.storage, &id);
-        let mut account_info:String = escrow.account_info.clone();
-
-        let accounts: Vec<&str> = account_info.split(';').collect();
-        let mut exist:bool = false;
+    if info.sender != escrow.client {
+        return Err(ContractError::NotClient {});
+    }
+    let funded = escrow.is_funded();
 
-        let mut newaccoount_info:String = String::from("");
-        for account in accounts {
-            let infos:Vec<&str> = account.split(':').collect();
-            if infos.len() == 4 && deps.api.addr_validate(&infos[0])? != info.sender{
-                newaccoount_info = newaccoount_info + ";" + account;
-            }
-            if infos.len() != 4 || deps.api.addr_validate(&infos[0])? != info.sender {
-                continue;
+    let mut changes: Vec<FieldChange> = vec![];
+    if let Some(title) = msg.work_title {
+        if title != escrow.work_title {
+            if funded {
+                return Err(ContractError::LockedAfterFunding { field: String::from("work_title") });
             }
-            let sender:Addr = deps.api.addr_validate(&infos[0])?;
-            let cwval = infos[1];
-            let start_time = infos[2];
-            let end_time = infos[3];
-            exist = true;
-
-            if env.block.time < Timestamp::from_seconds(end_time.parse().unwrap()) &&
-            env.block.time > Timestamp::from_seconds(start_time.parse().unwrap())
-            {
-                return Err(ContractError::AccountNotExpired {});
-            } else {
-                let addr:Addr = escrow.balance.cw20.get(0).unwrap().address.clone();
-                let token_account = Cw20CoinVerified {
-                    address: addr.clone(),
-                    amount: cwval.parse().unwrap()
-                };
-        
-                let balance_account = GenericBalance {
-                    native: vec![],
-                    cw20: vec![token_account],
-                };
-                
-                messages = send_tokens(&sender, &balance_account)?;
-
-                //remove tokens from escrow.balance
-                escrow.balance.sub_tokens(Balance::Cw20(Cw20CoinVerified {
-                    address: addr.clone(),
-                    amount: cwval.parse().unwrap()
-                }));
+            changes.push(FieldChange {
+                field: String::from("work_title"),
+                old: std::mem::replace(&mut escrow.work_title, title.clone()),
+                new: title,
+            });
+        }
+    }
+    if let Some(hash) = msg.work_url_hash {
+        let hash = check_hash(&hash)?;
+        if hash != escrow.work_url_hash {
+            if funded {
+                return Err(ContractError::LockedAfterFunding { field: String::from("work_url_hash") });
             }
-            
+            changes.push(FieldChange {
+                field: String::from("work_url_hash"),
+                old: std::mem::replace(&mut escrow.work_url_hash, hash.clone()),
+                new: hash,
+            });
         }
-
-        if !exist {
-            return Err(ContractError::DidntStaked {});
-        } 
-        escrow.account_info = newaccoount_info;
-        if escrow.balance.cw20.get(0).unwrap().amount >= Uint128::from(escrow.stake_amount) /*&& escrow.is_expired(&env)*/ {
-            escrow.state = 1; //set to started state
-        } else {
-            escrow.state = 0;
+    }
+    if let Some(desc) = msg.work_desc {
+        if desc != escrow.work_desc {
+            changes.push(FieldChange {
+                field: String::from("work_desc"),
+                old: std::mem::replace(&mut escrow.work_desc, desc.clone()),
+                new: desc,
+            });
+        }
+    }
+    if let Some(image) = msg.image_url {
+        if image != escrow.image_url {
+            changes.push(FieldChange {
+                field: String::from("image_url"),
+                old: std::mem::replace(&mut escrow.image_url, image.clone()),
+                new: image,
+            });
         }
+    }
 
+    if !changes.is_empty() {
         ESCROWS.save(deps.storage, &id, &escrow)?;
-        Ok(Response::new()
-        .add_attribute("action", "refund")
-        .add_attribute("id", id)
-        .add_attribute("to", info.sender)
-        .add_submessages(messages))
+        let mut history = WORK_HISTORY.may_load(deps.storage, &id)?.unwrap_or_default();
+        history.push(WorkEdit {
+            editor: info.sender,
+            time: env.block.time.seconds(),
+            funded,
+            changes,
+        });
+        WORK_HISTORY.save(deps.storage, &id, &history)?;
     }
-}
 
+    Ok(Response::new()
+        .add_attribute("action", "update_work")
+        .add_attribute("id", id))
+}
 
-pub fn execute_remove(
+pub fn execute_claim(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    id: String,
 ) -> Result<Response, ContractError> {
-    // this fails is no escrow there
-
-    let mut escrow = ESCROWS.load(deps.storage, &id)?;
-    let manager_addr:String = CONSTANT.load(deps.storage, "manager_addr")?;
-    let maddr:Addr = deps.api.addr_validate(&manager_addr)?;
+    settle_emission(deps.storage, &env, &info.sender)?;
+    let mut claimable = CLAIMABLE
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    CLAIMABLE.remove(deps.storage, &info.sender);
 
-    if escrow.state != 3 {
-        Err(ContractError::NotFinished {})
-    } else if info.sender != maddr {
-        Err(ContractError::NotManager {})
+    // matured refunds are paid out together with the claimable balance
+    let (matured, pending): (Vec<_>, Vec<_>) = UNBONDING
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|claim| claim.release_at.is_expired(&env.block));
+    for claim in matured.iter() {
+        claimable.try_add_balance(&claim.balance)?;
+    }
+    if pending.is_empty() {
+        UNBONDING.remove(deps.storage, &info.sender);
     } else {
-        // we delete the escrow
-        ESCROWS.remove(deps.storage, &id);
+        UNBONDING.save(deps.storage, &info.sender, &pending)?;
+    }
 
-        Ok(Response::new()
-        .add_attribute("action", "remove")
-        .add_attribute("id", id))
+    if claimable.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    // a failing transfer reverts the claim and leaves the balance claimable
+    let messages = send_tokens(&info.sender, &claimable)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim")
+        .add_attribute("to", info.sender)
+        .add_submessages(messages))
+}
+
+/// Pushes the balance to `to`, but a failing transfer does not revert the
+/// caller: the reply moves the tokens into the recipient's claimable balance.
+/// Replies come back on success too so the pending entry can be dropped.
+fn send_tokens_or_claim(
+    storage: &mut dyn Storage,
+    to: &Addr,
+    balance: &GenericBalance,
+) -> StdResult<Vec<SubMsg>> {
+    let mut parts: Vec<GenericBalance> = vec![];
+    if !balance.native.is_empty() {
+        parts.push(GenericBalance {
+            native: balance.native.clone(),
+            cw20: vec![],
+        });
+    }
+    for token in balance.cw20.iter() {
+        parts.push(GenericBalance {
+            native: vec![],
+            cw20: vec![token.clone()],
+        });
+    }
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    for part in parts {
+        let id = TRANSFER_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+        TRANSFER_SEQ.save(storage, &id)?;
+        for msg in send_tokens(to, &part)? {
+            msgs.push(SubMsg::reply_always(msg.msg, id));
+        }
+        PENDING_TRANSFERS.save(storage, id, &PendingTransfer {
+            recipient: to.clone(),
+            balance: part,
+        })?;
+    }
+    Ok(msgs)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let pending = PENDING_TRANSFERS.load(deps.storage, msg.id)?;
+    PENDING_TRANSFERS.remove(deps.storage, msg.id);
+
+    match msg.result {
+        ContractResult::Ok(_) => Ok(Response::new()),
+        ContractResult::Err(err) => {
+            add_claimable(deps.storage, &pending.recipient, &pending.balance)?;
+            Ok(Response::new()
+                .add_attribute("action", "transfer_failed")
+                .add_attribute("to", pending.recipient)
+                .add_attribute("error", err))
+        }
     }
 }
 
@@ -456,6 +2670,27 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::DetailsAll {addr} => to_binary(&query_detailsall(deps, env, addr)?),
         QueryMsg::Constants {} => to_binary(&query_constants(deps)?),
         QueryMsg::IsAdmin {addr} => to_binary(&query_isadmin(deps, addr)?),
+        QueryMsg::PauseState {} => to_binary(&query_pause(deps)?),
+        QueryMsg::WorkHistory { id } => to_binary(&query_work_history(deps, id)?),
+        QueryMsg::Tokens {} => to_binary(&query_tokens(deps)?),
+        QueryMsg::Claimable { addr } => to_binary(&query_claimable(deps, addr)?),
+        QueryMsg::Unbonding { addr } => to_binary(&query_unbonding(deps, env, addr)?),
+        QueryMsg::ListingFee { addr } => to_binary(&query_listing_fee(deps, addr)?),
+        QueryMsg::Split {} => to_binary(&query_split(deps)?),
+        QueryMsg::AccessPass { id, addr } => to_binary(&query_access_pass(deps, id, addr)?),
+        QueryMsg::Emission {} => to_binary(&query_emission(deps, env)?),
+        QueryMsg::PendingEmission { addr } => to_binary(&query_pending_emission(deps, env, addr)?),
+        QueryMsg::Rewards { id, addr } => to_binary(&query_rewards(deps, env, id, addr)?),
+        QueryMsg::PreviewPayouts { id } => to_binary(&query_preview_payouts(deps, id)?),
+        QueryMsg::Position { id, addr } => to_binary(&query_position(deps, id, addr)?),
+        QueryMsg::Waitlist { id } => to_binary(&WaitlistResponse {
+            entries: WAITLISTS.may_load(deps.storage, &id)?.unwrap_or_default(),
+        }),
+        QueryMsg::Quote { id } => to_binary(&query_quote(deps, id)?),
+        QueryMsg::Commission { id, addr } => to_binary(&query_commission(deps, id, addr)?),
+        QueryMsg::Listings { start_after, limit } => {
+            to_binary(&query_listings(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -468,13 +2703,24 @@ fn query_list(deps: Deps) -> StdResult<ListResponse> {
 fn query_detailsall(deps: Deps, env: Env, addr:String) -> StdResult<DetailsAllResponse> {
     let ids:Vec<String> = all_escrow_ids(deps.storage)?;
 
-    let isadmin:bool = CONSTANT.load(deps.storage, "manager_addr")? == addr || CONSTANT.load(deps.storage, "manager_addr")? == "";
+    let isadmin:bool = !manager_configured(deps)? || is_manager(deps, &Addr::unchecked(&addr))?;
 
     let mut ret:Vec<DetailsResponse> = vec![];
 
     for idstr in ids {
         let escrow = ESCROWS.load(deps.storage, idstr.as_str())?;
         let expired:bool = escrow.is_expired(&env);
+        let flagged_stake = flagged_stake(&escrow)?;
+        let totals = GenericAccount::parse(&escrow.account_info)?.totals();
+        let stakers = totals.len() as u32;
+        let waitlisted = WAITLISTS.may_load(deps.storage, &idstr)?.unwrap_or_default().len() as u32;
+        let my_total = totals
+            .iter()
+            .find(|(staker, _)| staker.as_str() == addr)
+            .map(|(_, amount)| *amount)
+            .unwrap_or_default();
+        let my_allowlisted = escrow.allowlist == Some(Allowlist::Addresses)
+            && ALLOWLISTS.has(deps.storage, (&idstr, &Addr::unchecked(&addr)));
         //let expired:bool = false;
         let cw20_balance: StdResult<Vec<_>> = escrow
             .balance
@@ -488,36 +2734,92 @@ fn query_detailsall(deps: Deps, env: Env, addr:String) -> StdResult<DetailsAllRe
             })
             .collect();
         
-        let mut accountinfo:String = String::from(escrow.account_info);
-        let (my_staked_all, my_staked) = accountStaked(deps, &accountinfo, deps.api.addr_validate(&addr)?);
+        let mut accountinfo:String = escrow.account_info;
+        let (my_staked_all, my_staked) = account_staked(deps, &accountinfo, deps.api.addr_validate(&addr)?);
         
         if !isadmin {
             accountinfo = my_staked_all;
         }
 
+        let work_url_revealed = !escrow.work_url.is_empty();
         let mut workurl = String::from("");
-        if isadmin || escrow.state > 0 && my_staked != String::from("") && expired || escrow.client == addr {
+        // the delivered url is shown on the same terms as the work url
+        let url_visible = isadmin || escrow.state > 0 && !my_staked.is_empty() && expired || escrow.client == addr;
+        if url_visible {
             workurl = escrow.work_url;
         }
+        let deliverable = escrow.deliverable.map(|deliverable| Deliverable {
+            url: if url_visible { deliverable.url } else { String::new() },
+            ..deliverable
+        });
         let mut cw20balance = vec![];
         if isadmin {
             cw20balance = cw20_balance?;
         }
         
+        let my_staked_display = match my_staked.parse::<Uint128>() {
+            Ok(amount) => display_amount(amount, escrow.stake_decimals),
+            Err(_) => String::from(""),
+        };
+
         let details = DetailsResponse {
             id: idstr,
             client: escrow.client.into(),
             work_title: escrow.work_title,
             work_desc: escrow.work_desc,
             work_url: workurl,
+            work_url_hash: escrow.work_url_hash,
+            work_url_revealed,
             start_time: escrow.start_time,
             account_min_stake_amount: escrow.account_min_stake_amount,
             stake_amount: escrow.stake_amount,
             cw20_balance: cw20balance,
+            reward_pool: to_cw20_coins(&escrow.reward_pool),
+            client_bond_native: escrow.client_bond.native,
+            client_bond: escrow
+                .client_bond
+                .cw20
+                .into_iter()
+                .map(|token| Cw20Coin {
+                    address: token.address.into(),
+                    amount: token.amount,
+                })
+                .collect(),
+            arbiter: escrow.arbiter.map(|a| a.into()),
+            collaborators: escrow
+                .collaborators
+                .into_iter()
+                .map(|c| CollaboratorResponse {
+                    member: c.member.into(),
+                    payout: c.payout.into(),
+                    weight: c.weight,
+                })
+                .collect(),
+            stake_token: escrow.stake_token.into(),
+            stake_amount_display: display_amount(escrow.stake_amount, escrow.stake_decimals),
+            account_min_stake_amount_display: display_amount(escrow.account_min_stake_amount, escrow.stake_decimals),
+            stake_symbol: escrow.stake_symbol,
+            stake_decimals: escrow.stake_decimals,
             account_info: accountinfo,
             state: escrow.state,
-            my_staked: my_staked,
-            expired: expired,
+            closed: escrow.closed,
+            my_allowlisted,
+            allowlist: escrow.allowlist,
+            group: escrow.group,
+            max_stake_per_address: escrow.max_stake_per_address,
+            max_stakers: escrow.max_stakers,
+            pricing: escrow.pricing,
+            stakers,
+            remaining_stakers: escrow.max_stakers.map(|max| max.saturating_sub(stakers)),
+            waitlisted,
+            my_remaining_stake: escrow
+                .max_stake_per_address
+                .map(|max| max.saturating_sub(my_total)),
+            deliverable,
+            flagged_stake,
+            my_staked,
+            my_staked_display,
+            expired,
             timestamp: env.block.time.seconds().to_string(),
             image_url: escrow.image_url
         };
@@ -529,6 +2831,23 @@ fn query_detailsall(deps: Deps, env: Env, addr:String) -> StdResult<DetailsAllRe
     })
 }
 
+/// Formats a raw token amount with the token's decimals, trailing zeros trimmed
+fn display_amount(amount: Uint128, decimals: u8) -> String {
+    let raw = amount.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return raw;
+    }
+    let padded = format!("{:0>width$}", raw, width = decimals + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
 fn query_constants(deps: Deps) -> StdResult<ConstantMsg> {
 
     Ok(ConstantMsg {
@@ -536,14 +2855,339 @@ fn query_constants(deps: Deps) -> StdResult<ConstantMsg> {
         min_stake: CONSTANT.load(deps.storage, "min_stake")?,
         rate_client: CONSTANT.load(deps.storage, "rate_client")?,
         rate_manager: CONSTANT.load(deps.storage, "rate_manager")?,
+        unbonding_period: Some(
+            CONSTANT
+                .may_load(deps.storage, "unbonding_period")?
+                .unwrap_or_else(|| String::from("0")),
+        ),
+        royalty_bps: Some(
+            CONSTANT
+                .may_load(deps.storage, "royalty_bps")?
+                .unwrap_or_else(|| String::from("0")),
+        ),
+        review_window: Some(
+            CONSTANT
+                .may_load(deps.storage, "review_window")?
+                .unwrap_or_else(|| String::from("0")),
+        ),
+        delivery_timeout: Some(
+            CONSTANT
+                .may_load(deps.storage, "delivery_timeout")?
+                .unwrap_or_else(|| String::from("0")),
+        ),
+        flag_threshold_bps: Some(
+            CONSTANT
+                .may_load(deps.storage, "flag_threshold_bps")?
+                .unwrap_or_else(|| String::from("0")),
+        ),
     })
 }
 
 fn query_isadmin(deps: Deps, addr: String) -> StdResult<IsAdminResponse> {
 
-    let manager_addr:String  = CONSTANT.load(deps.storage, "manager_addr")?;
-
     Ok(IsAdminResponse {
-        isadmin: manager_addr == "" || manager_addr == addr,
+        isadmin: !manager_configured(deps)? || is_manager(deps, &Addr::unchecked(addr))?,
+    })
+}
+
+fn query_pause(deps: Deps) -> StdResult<PauseResponse> {
+    let pause = PAUSE.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(PauseResponse {
+        create: pause.create,
+        top_up: pause.top_up,
+        receive: pause.receive,
+    })
+}
+
+fn query_work_history(deps: Deps, id: String) -> StdResult<WorkHistoryResponse> {
+    // make sure the work exists, history alone may be empty
+    ESCROWS.load(deps.storage, &id)?;
+    let edits = WORK_HISTORY.may_load(deps.storage, &id)?.unwrap_or_default();
+
+    Ok(WorkHistoryResponse { id, edits })
+}
+
+fn query_tokens(deps: Deps) -> StdResult<TokensResponse> {
+    let tokens: StdResult<Vec<_>> = STAKE_TOKENS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (address, token) = item?;
+            Ok(TokenResponse {
+                address: address.into(),
+                min_stake: token.min_stake,
+                enabled: token.enabled,
+            })
+        })
+        .collect();
+
+    Ok(TokensResponse { tokens: tokens? })
+}
+
+fn query_claimable(deps: Deps, addr: String) -> StdResult<ClaimableResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let claimable = CLAIMABLE.may_load(deps.storage, &addr)?.unwrap_or_default();
+
+    Ok(ClaimableResponse {
+        native: claimable.native,
+        cw20: claimable
+            .cw20
+            .into_iter()
+            .map(|token| Cw20Coin {
+                address: token.address.into(),
+                amount: token.amount,
+            })
+            .collect(),
+    })
+}
+
+fn query_unbonding(deps: Deps, env: Env, addr: String) -> StdResult<UnbondingResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let claims = UNBONDING.may_load(deps.storage, &addr)?.unwrap_or_default();
+
+    let mut pending: Vec<UnbondingClaimResponse> = vec![];
+    let mut matured: Vec<UnbondingClaimResponse> = vec![];
+    for claim in claims {
+        let is_matured = claim.release_at.is_expired(&env.block);
+        let item = UnbondingClaimResponse {
+            id: claim.id,
+            native: claim.balance.native,
+            cw20: claim
+                .balance
+                .cw20
+                .into_iter()
+                .map(|token| Cw20Coin {
+                    address: token.address.into(),
+                    amount: token.amount,
+                })
+                .collect(),
+            release_at: claim.release_at,
+        };
+        if is_matured {
+            matured.push(item);
+        } else {
+            pending.push(item);
+        }
+    }
+
+    Ok(UnbondingResponse { pending, matured })
+}
+
+fn query_listing_fee(deps: Deps, addr: Option<String>) -> StdResult<ListingFeeResponse> {
+    let fee = LISTING_FEE.may_load(deps.storage)?.map(|fee| ListingFeeMsg {
+        amount: fee.amount,
+        denom: fee.denom,
+        collector: fee.collector.into(),
+    });
+    let (exempt, prepaid) = match addr {
+        Some(addr) => {
+            let addr = deps.api.addr_validate(&addr)?;
+            (
+                FEE_EXEMPT.has(deps.storage, &addr),
+                FEE_CREDITS.may_load(deps.storage, &addr)?.unwrap_or_default(),
+            )
+        }
+        None => (false, 0),
+    };
+
+    Ok(ListingFeeResponse { fee, exempt, prepaid })
+}
+
+fn query_split(deps: Deps) -> StdResult<SplitResponse> {
+    Ok(SplitResponse {
+        default: split_to_msg(DEFAULT_SPLIT.load(deps.storage)?),
+        limits: SPLIT_LIMITS.load(deps.storage)?,
+    })
+}
+
+fn query_preview_payouts(deps: Deps, id: String) -> StdResult<PayoutsResponse> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    let manager = manager_payee(deps)?.map(String::from).unwrap_or_default();
+
+    let mut payouts: Vec<Payout> = vec![];
+    let mut paid = Uint128::zero();
+    if escrow.state <= 1 {
+        for (role, address, amount) in stake_payouts(&escrow)? {
+            paid += amount;
+            payouts.push(Payout {
+                role: role.to_string(),
+                address: address.into(),
+                amount,
+            });
+        }
+    }
+    if escrow.state <= 2 {
+        payouts.push(Payout {
+            role: String::from("manager"),
+            address: manager,
+            amount: escrow.staked().checked_sub(paid)?,
+        });
+    }
+
+    Ok(PayoutsResponse {
+        id,
+        token: escrow.stake_token.into(),
+        payouts,
+    })
+}
+
+fn to_cw20_coins(balance: &GenericBalance) -> Vec<Cw20Coin> {
+    balance
+        .cw20
+        .iter()
+        .map(|token| Cw20Coin {
+            address: token.address.to_string(),
+            amount: token.amount,
+        })
+        .collect()
+}
+
+fn query_rewards(deps: Deps, env: Env, id: String, addr: String) -> StdResult<RewardsResponse> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    let addr = deps.api.addr_validate(&addr)?;
+
+    let shares = split_pro_rata(&escrow.reward_pool, &reward_weights(&escrow, env.block.time.seconds())?)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let share = shares
+        .into_iter()
+        .find(|(staker, _)| *staker == addr)
+        .map(|(_, share)| to_cw20_coins(&share))
+        .unwrap_or_default();
+
+    Ok(RewardsResponse {
+        id,
+        reward_pool: to_cw20_coins(&escrow.reward_pool),
+        share,
+        settled: escrow.state > 1,
+    })
+}
+
+fn query_emission(deps: Deps, env: Env) -> StdResult<EmissionResponse> {
+    let emission = load_emission(deps.storage, env.block.time.seconds())?;
+
+    Ok(EmissionResponse {
+        reward_token: emission.reward_token.into(),
+        stake_token: emission.stake_token.into(),
+        rate: emission.rate,
+        period_finish: emission.period_finish,
+        total_staked: emission.total_staked,
+    })
+}
+
+fn query_pending_emission(deps: Deps, env: Env, addr: String) -> StdResult<PendingEmissionResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let now = env.block.time.seconds();
+    let mut emission = load_emission(deps.storage, now)?;
+    emission.update(now)?;
+    let mut info = STAKER_EMISSIONS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    info.settle(emission.reward_per_token)?;
+
+    Ok(PendingEmissionResponse {
+        reward_token: emission.reward_token.into(),
+        staked: info.staked,
+        pending: info.pending,
+    })
+}
+
+fn query_access_pass(deps: Deps, id: String, addr: String) -> StdResult<AccessPassResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+
+    Ok(AccessPassResponse {
+        nft_contract: ACCESS_PASS_NFT.may_load(deps.storage)?.map(|a| a.into()),
+        token_id: ACCESS_PASSES.may_load(deps.storage, (&id, &addr))?,
+    })
+}
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+fn query_listings(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<ListingsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let listings = LISTINGS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (listing_id, listing) = item?;
+            Ok(ListingResponse {
+                listing_id,
+                id: listing.id,
+                seller: listing.seller.into(),
+                amount: listing.amount,
+                price: listing.price,
+                denom: listing.denom,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListingsResponse { listings })
+}
+
+fn query_commission(deps: Deps, id: String, addr: Option<String>) -> StdResult<CommissionResponse> {
+    let commission = COMMISSIONS.load(deps.storage, &id)?;
+    // the url is for the parties only, everyone else sees the content hash
+    let party = addr.is_some_and(|addr| {
+        let addr = Addr::unchecked(addr);
+        commission.client == addr || commission.artist == addr || commission.arbiter == Some(addr)
+    });
+    let deliverable = commission.deliverable.map(|deliverable| Deliverable {
+        url: if party { deliverable.url } else { String::new() },
+        ..deliverable
+    });
+
+    Ok(CommissionResponse {
+        id,
+        client: commission.client.into(),
+        artist: commission.artist.into(),
+        arbiter: commission.arbiter.map(|a| a.into()),
+        work_title: commission.work_title,
+        work_desc: commission.work_desc,
+        cw20_balance: to_cw20_coins(&commission.balance),
+        native_balance: commission.balance.native,
+        submit_deadline: commission.submit_deadline,
+        review_period: commission.review_period,
+        deliverable,
+        status: commission.status,
+    })
+}
+
+fn query_position(deps: Deps, id: String, addr: String) -> StdResult<PositionResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    let staked = GenericAccount::parse(&escrow.account_info)?
+        .totals()
+        .into_iter()
+        .find(|(staker, _)| *staker == addr)
+        .map(|(_, amount)| amount)
+        .unwrap_or_default();
+
+    Ok(PositionResponse {
+        pubkey: STAKER_PUBKEYS.may_load(deps.storage, (&id, &addr))?,
+        encrypted_url: ENCRYPTED_URLS.may_load(deps.storage, (&id, &addr))?,
+        id,
+        addr: addr.into(),
+        staked,
+    })
+}
+
+fn query_quote(deps: Deps, id: String) -> StdResult<QuoteResponse> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    let slots = GenericAccount::parse(&escrow.account_info)?.totals().len() as u32;
+    let min_stake = STAKE_TOKENS
+        .may_load(deps.storage, &escrow.stake_token)?
+        .map(|token| token.min_stake)
+        .unwrap_or_default();
+    let price = match &escrow.pricing {
+        Some(pricing) => pricing.price(slots)?.max(min_stake),
+        None => min_stake,
+    };
+
+    Ok(QuoteResponse {
+        id,
+        slots,
+        price,
+        price_display: display_amount(price, escrow.stake_decimals),
+        pricing: escrow.pricing,
     })
 }
@@ -36,9 +36,15 @@ pub enum ContractError {
     #[error("You did not stake")]
     DidntStaked {},
 
-    #[error("Only accepts tokens in the cw20_whitelist")]
+    #[error("Only accepts the stake token of this work")]
     NotInWhitelist {},
 
+    #[error("Token is not registered as a stake token")]
+    TokenNotRegistered {},
+
+    #[error("Stake token is disabled")]
+    TokenDisabled {},
+
     #[error("NotCrewToken")]
     NotCrew {},
 
@@ -51,6 +57,150 @@ pub enum ContractError {
     #[error("Send some coins to create an escrow")]
     EmptyBalance {},
 
+    #[error("Insufficient balance held for this work")]
+    InsufficientBalance {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Listing fee is not paid")]
+    ListingFeeNotPaid {},
+
+    #[error("Client still has time to deliver")]
+    DeliveryNotMissed {},
+
+    #[error("No listing fee is configured")]
+    NoListingFee {},
+
+    #[error("Listing fee must be native or the crew token")]
+    InvalidFeeDenom {},
+
+    #[error("No client bond left")]
+    NoBond {},
+
+    #[error("Work is closed")]
+    WorkClosed {},
+
+    #[error("Split shares must sum to 10000 basis points")]
+    InvalidSplit {},
+
+    #[error("Split is outside the limits set by the manager")]
+    SplitOutOfBounds {},
+
+    #[error("Collaborators need distinct addresses and non zero weights")]
+    InvalidCollaborators {},
+
+    #[error("You are not a collaborator of this work")]
+    NotCollaborator {},
+
+    #[error("Work is already settled")]
+    AlreadySettled {},
+
+    #[error("Emissions are paid in another token")]
+    WrongRewardToken {},
+
+    #[error("Emission duration must be positive")]
+    InvalidDuration {},
+
+    #[error("Deadline or review period is out of range")]
+    InvalidDeadline {},
+
+    #[error("Position can not be transferred once the work is approved")]
+    NotTransferable {},
+
+    #[error("Transfer needs a non zero amount and another recipient")]
+    InvalidTransfer {},
+
+    #[error("Stake on open listings can not be listed again or transferred")]
+    StakeListed {},
+
+    #[error("Denom is not accepted on the market")]
+    DenomNotAccepted {},
+
+    #[error("Payment does not match the listing price")]
+    WrongPayment {},
+
+    #[error("Listing price must be positive")]
+    InvalidPrice {},
+
+    #[error("Royalty can not exceed 10000 basis points")]
+    InvalidRoyalty {},
+
+    #[error("You are not the seller")]
+    NotSeller {},
+
+    #[error("You are not the artist")]
+    NotArtist {},
+
+    #[error("Commission is not {status}")]
+    WrongCommissionStatus { status: String },
+
+    #[error("Submission deadline has passed")]
+    DeadlinePassed {},
+
+    #[error("Commission has not timed out yet")]
+    NotTimedOut {},
+
+    #[error("Submit a deliverable first")]
+    NoDeliverable {},
+
+    #[error("Deliverable is still in its review window")]
+    ReviewWindowOpen {},
+
+    #[error("Review window is closed")]
+    ReviewWindowClosed {},
+
+    #[error("Deliverable was flagged by too much of the stake")]
+    DeliverableFlagged {},
+
+    #[error("You already flagged this deliverable")]
+    AlreadyFlagged {},
+
+    #[error("Flag threshold can not exceed 10000 basis points")]
+    InvalidFlagThreshold {},
+
+    #[error("Work url hash must be a hex encoded sha256")]
+    InvalidHash {},
+
+    #[error("Work url does not match the committed hash")]
+    HashMismatch {},
+
+    #[error("Work url is already revealed")]
+    AlreadyRevealed {},
+
+    #[error("Public key must be a 33 or 65 byte secp256k1 key")]
+    InvalidPubkey {},
+
+    #[error("No encrypted url for staker {staker}")]
+    MissingBlob { staker: String },
+
+    #[error("{address} is not a staker of this work")]
+    NotStaker { address: String },
+
+    #[error("Staker {staker} has no registered key")]
+    NoPubkey { staker: String },
+
+    #[error("You are not on the allowlist of this work")]
+    NotAllowlisted {},
+
+    #[error("Work has no address allowlist")]
+    NoAddressAllowlist {},
+
+    #[error("You are not a member of the group of this work")]
+    NotGroupMember {},
+
+    #[error("Stake exceeds the cap of your group weight")]
+    GroupCapExceeded {},
+
+    #[error("Stake exceeds the per address cap of this work")]
+    StakeCapExceeded {},
+
+    #[error("Work has reached its maximum number of stakers")]
+    TooManyStakers {},
+
+    #[error("You are not on the waitlist")]
+    NotWaitlisted {},
+
     #[error("Stake is ended")]
     StakeFinished {},
 
@@ -62,4 +212,10 @@ pub enum ContractError {
 
     #[error("Insufficient token amount for stake.")]
     InsufficientTopUp {},
+
+    #[error("{scope} is paused")]
+    Paused { scope: String },
+
+    #[error("{field} can not be changed after the work is funded")]
+    LockedAfterFunding { field: String },
 }
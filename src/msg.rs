@@ -1,9 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Api, Coin, StdResult};
-use crate::state::{AccountInfo};
-use cw20::{Cw20Coin, Cw20ReceiveMsg};
+use cosmwasm_std::{Binary, Coin, Uint128};
+use crate::state::{Allowlist, BondingCurve, CommissionStatus, Deliverable, GroupGate, WaitlistEntry, SplitLimits, WorkEdit};
+use cw_utils::Expiration;
+use cw20::{Cw20Coin, Cw20ReceiveMsg, Denom};
 
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct InstantiateMsg {
@@ -12,6 +13,7 @@ pub struct InstantiateMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     Create(CreateMsg),
     /// Adds all sent native tokens to the contract
@@ -23,27 +25,231 @@ pub enum ExecuteMsg {
         id: String,
     },
     /// Refund returns all remaining tokens to the original sender,
-    /// The client can do this any time, or anyone can do this after a timeout
+    /// The client can do this any time, or anyone can do this after a timeout.
+    /// Stakers of a closed work can always refund
     Refund {
         /// id is a human-readable name for the escrow from create
         id: String,
     },
+    /// Only the manager can do this, once the work is settled
+    /// or closed with every stake refunded
     Remove {
         id: String,
     },
+    /// Edit the work metadata. Only the client can do this.
+    /// Title and url are locked once the work is funded
+    UpdateWork(UpdateWorkMsg),
+    /// Changes where the sender's part of the client share is paid.
+    /// Only a collaborator of the work can do this
+    SetPayoutAddress {
+        id: String,
+        address: String,
+    },
+    /// Moves all or part of the sender's stake, with its refund and reward rights,
+    /// to recipient. Only possible until the client approves, and only to a
+    /// recipient that could top up the work itself. Moving the whole position
+    /// hands over the sender's access pass, which needs this contract approved on it
+    TransferPosition {
+        id: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Offers amount of the sender's stake for a fixed price.
+    /// The position stays with the seller until it is bought, but it can not
+    /// be listed again or transferred meanwhile. A refund or the client's
+    /// approval drops the listing. Selling the whole position hands over the
+    /// seller's access pass, which needs this contract approved on it
+    ListPosition {
+        id: String,
+        amount: Uint128,
+        price: Uint128,
+        denom: Denom,
+    },
+    /// Only the seller can do this
+    CancelListing {
+        listing_id: u64,
+    },
+    /// Buys a listing priced in a native denom with the sent funds
+    BuyPosition {
+        listing_id: u64,
+    },
+    /// Hands in the finished work. Only the client can do this, once funded.
+    /// Submitting again replaces the deliverable and clears its flags
+    SubmitDeliverable {
+        id: String,
+        url: String,
+        content_hash: String,
+    },
+    /// Objects to the deliverable during its review window.
+    /// Only a staker can do this
+    FlagDeliverable {
+        id: String,
+    },
+    /// Publishes the work url committed at create. Only the client can do
+    /// this, once funded, and the url must match the committed hash
+    RevealWorkUrl {
+        id: String,
+        work_url: String,
+    },
+    /// Registers the key the client encrypts the work url to.
+    /// Only a staker of the work can do this. The key and its encrypted url
+    /// go away once the staker has no stake left in the work
+    RegisterPubkey {
+        id: String,
+        pubkey: Binary,
+    },
+    /// Replaces the encrypted work urls. Only the client can do this, once
+    /// funded and every current staker registered a key, with exactly one
+    /// blob per staker
+    UploadEncryptedUrls {
+        id: String,
+        blobs: Vec<EncryptedUrlMsg>,
+    },
+    /// Replaces the allowlist, None opens the work to everyone.
+    /// Only the client can do this
+    SetAllowlist {
+        id: String,
+        allowlist: Option<AllowlistMsg>,
+    },
+    /// Edits an address allowlist. Only the client can do this
+    UpdateAllowlist {
+        id: String,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Takes back every deposit the sender has waiting for a slot
+    LeaveWaitlist {
+        id: String,
+    },
+    /// Splits the client bond and the reward pool between the stakers pro-rata
+    /// once the client missed the delivery timeout, and closes the work so the
+    /// stakers can refund. Only the arbiter or a configured manager can do this
+    SlashBond {
+        id: String,
+    },
+    /// Returns the bond and the reward pool of a work that expired without
+    /// reaching its goal to the client. Only the client can do this
+    WithdrawBond {
+        id: String,
+    },
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
     Receive(Cw20ReceiveMsg),
+    /// Sends the sender everything credited to them by approvals
+    /// and failed transfers
+    Claim {},
     /// Set Constant
     SetConstant(ConstantMsg),
-    
+    /// Pause or unpause deposits. Only the manager can do this
+    SetPause {
+        scope: PauseScope,
+        paused: bool,
+    },
+    /// Register a cw20 as stake token, or update an existing one.
+    /// Only the manager can do this
+    AddToken {
+        address: String,
+        min_stake: Uint128,
+        enabled: bool,
+    },
+    /// Only the manager can do this. Running works keep taking top ups
+    /// and rewards in their token, without a min_stake
+    RemoveToken {
+        address: String,
+    },
+    /// Set or clear (None) the fee charged on create.
+    /// Only the manager can do this
+    SetListingFee {
+        fee: Option<ListingFeeMsg>,
+    },
+    /// Pays the listing fee for one later create with the sent native funds.
+    /// Lets a create in one denom cover a fee in the other
+    PayListingFee {},
+    /// Split used by works created without their own.
+    /// Only the manager can do this
+    SetDefaultSplit(RevenueSplitMsg),
+    /// Only the manager can do this
+    SetSplitLimits(SplitLimits),
+    /// Set or clear the cw721 contract access passes are minted on.
+    /// Only the manager can do this
+    SetAccessPassNft {
+        address: Option<String>,
+    },
+    /// Let the admin of a cw4 group act as manager, None to stop.
+    /// Only the manager can do this
+    SetManagerGroup {
+        group: Option<String>,
+    },
+    /// Allow or forbid a native denom as listing price.
+    /// Only the manager can do this
+    SetMarketDenom {
+        denom: String,
+        enabled: bool,
+    },
+    /// Pays the sent funds to the artist once the deliverable is approved
+    CreateCommission(CreateCommissionMsg),
+    /// Only the artist can do this, before the submit deadline
+    SubmitCommission {
+        id: String,
+        url: String,
+        content_hash: String,
+    },
+    /// Pays the artist. Only the client or the arbiter can do this
+    ApproveCommission {
+        id: String,
+    },
+    /// Refunds the client. Only the client or the arbiter can do this, and only
+    /// after the submit deadline while nothing is submitted. A submission can
+    /// only be rejected by the arbiter, or by the client when there is none
+    RejectCommission {
+        id: String,
+    },
+    /// Refunds the client after a missed deadline, or pays the artist
+    /// after an unanswered review period. Anyone can do this
+    ClaimCommissionTimeout {
+        id: String,
+    },
+    /// Only the manager can do this
+    AddFeeExemption {
+        address: String,
+    },
+    /// Only the manager can do this
+    RemoveFeeExemption {
+        address: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub enum PauseScope {
+    /// Every deposit path at once
+    All,
+    Create,
+    TopUp,
+    /// Any cw20 deposit, whatever the embedded message
+    Receive,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ReceiveMsg {
     Create(CreateMsg),
     /// Adds all sent native tokens to the contract
     TopUp(TopUpMsg),
+    /// Adds the sent tokens to the reward pool of the work.
+    /// Anyone can do this until the client approves. The pool is split by
+    /// stake times seconds staked until approval, capped at one year. It goes
+    /// to the stakers with a slashed bond, or to the client with a withdrawn one
+    FundRewards { id: String },
+    /// Streams the sent tokens to all active stakes over `duration` seconds,
+    /// together with what is left of the running period. The stream is held
+    /// while nothing is staked. Only the manager can do this
+    FundEmission { duration: u64 },
+    /// Buys a listing priced in the sent cw20
+    BuyPosition { listing_id: u64 },
+    /// Pays the listing fee for one later create with the sent crew tokens
+    PayListingFee {},
+    CreateCommission(CreateCommissionMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -52,39 +258,142 @@ pub struct CreateMsg {
     /// 3-20 bytes of utf-8 text
     pub id: String,
     pub client: String,
-    pub cw20_whitelist: Option<Vec<String>>,
+    /// Can slash the client bond together with the manager
+    pub arbiter: Option<String>,
+    /// Registered cw20 to stake in. Defaults to the token sent with
+    /// the create, or the crew token for native creates
+    pub stake_token: Option<String>,
     pub work_title: String,
     pub work_desc: String,
-    pub work_url: String,
+    /// Hex sha256 of the work url, which is revealed once funded
+    pub work_url_hash: String,
     pub start_time: Option<u64>,
-    pub account_min_stake_amount : u64,
-    pub stake_amount: u64,
-    pub image_url: String
+    pub account_min_stake_amount : Uint128,
+    pub stake_amount: Uint128,
+    pub image_url: String,
+    /// Overrides the default split, within the manager's limits
+    pub split: Option<RevenueSplitMsg>,
+    /// Shares the client share by weight. Include the client to keep a part
+    pub collaborators: Option<Vec<CollaboratorMsg>>,
+    /// Seconds refunds stay locked before they can be claimed.
+    /// Falls back to the contract wide unbonding_period
+    pub unbonding_period: Option<u64>,
+    /// Only these stakers can top up, everyone when omitted
+    pub allowlist: Option<AllowlistMsg>,
+    /// Only members of this cw4 group can top up
+    pub group: Option<GroupGateMsg>,
+    /// Most any address can stake, unlimited when omitted
+    pub max_stake_per_address: Option<Uint128>,
+    /// Most distinct stakers, unlimited when omitted
+    pub max_stakers: Option<u32>,
+    /// Raises the stake a new staker needs as the work fills
+    pub pricing: Option<BondingCurve>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupGateMsg {
+    pub address: String,
+    /// Caps a member's total stake at weight times this amount
+    pub stake_per_weight: Option<Uint128>,
+}
+
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreateCommissionMsg {
+    /// shares the 3-20 bytes id space with the staking works
+    pub id: String,
+    pub artist: String,
+    pub arbiter: Option<String>,
+    pub work_title: String,
+    pub work_desc: String,
+    /// Seconds the artist has to submit
+    pub submit_deadline: u64,
+    /// Seconds the client has to review a submission
+    pub review_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EncryptedUrlMsg {
+    pub staker: String,
+    pub ciphertext: Binary,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TopUpMsg {
     pub id: String,
     pub start_time: u64,
-    pub end_time: u64
+    pub end_time: u64,
+    /// Hex sibling hashes from the sender's leaf up, for Merkle root allowlists
+    pub proof: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowlistMsg {
+    Addresses(Vec<String>),
+    /// Hex sha256 root over the sha256 of each address, pairs hashed in sorted order
+    MerkleRoot(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollaboratorMsg {
+    pub address: String,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SplitShareMsg {
+    pub address: String,
+    pub bps: u16,
+}
+
+/// Basis points, client + manager + stakers + extra shares must be 10000
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RevenueSplitMsg {
+    pub client_bps: u16,
+    pub manager_bps: u16,
+    pub stakers_bps: u16,
+    pub extra_shares: Vec<SplitShareMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListingFeeMsg {
+    pub amount: Uint128,
+    /// native denom or the crew cw20
+    pub denom: Denom,
+    pub collector: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UpdateWorkMsg {
+    pub id: String,
+    pub work_title: Option<String>,
+    pub work_desc: Option<String>,
+    pub work_url_hash: Option<String>,
+    pub image_url: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConstantMsg {
     pub manager_addr: String,
     pub min_stake: String,
+    /// Superseded by the default split, kept for old clients
     pub rate_client: String,
     pub rate_manager: String,
-}
-
-impl CreateMsg {
-    pub fn addr_whitelist(&self, api: &dyn Api) -> StdResult<Vec<Addr>> {
-        match self.cw20_whitelist.as_ref() {
-            Some(v) => v.iter().map(|h| api.addr_validate(h)).collect(),
-            None => Ok(vec![]),
-        }
-    }
+    /// Seconds, left unchanged when omitted
+    pub unbonding_period: Option<String>,
+    /// Basis points of every market sale paid to the client of the work,
+    /// left unchanged when omitted
+    pub royalty_bps: Option<String>,
+    /// Seconds stakers can flag a deliverable, left unchanged when omitted.
+    /// Applies to deliverables submitted after the change
+    pub review_window: Option<String>,
+    /// Seconds a funded work has to get a deliverable before its bond can be
+    /// slashed, left unchanged when omitted
+    pub delivery_timeout: Option<String>,
+    /// Basis points of the stake whose flags block the client approval,
+    /// 0 disables flagging. Left unchanged when omitted
+    pub flag_threshold_bps: Option<String>,
 }
 
 pub fn is_valid_name(name: &str) -> bool {
@@ -104,6 +413,45 @@ pub enum QueryMsg {
     DetailsAll {addr: String},
     Constants {},
     IsAdmin { addr: String},
+    /// Returns PauseResponse
+    PauseState {},
+    /// Returns WorkHistoryResponse
+    WorkHistory { id: String },
+    /// Returns TokensResponse
+    Tokens {},
+    /// Returns ClaimableResponse
+    Claimable { addr: String },
+    /// Returns UnbondingResponse
+    Unbonding { addr: String },
+    /// Returns ListingFeeResponse
+    ListingFee { addr: Option<String> },
+    /// Returns SplitResponse
+    Split {},
+    /// Returns AccessPassResponse
+    AccessPass { id: String, addr: String },
+    /// Returns EmissionResponse
+    Emission {},
+    /// Emission rewards addr can claim now. Returns PendingEmissionResponse
+    PendingEmission { addr: String },
+    /// Reward pool of a work and what addr would get if it settled now.
+    /// Returns RewardsResponse
+    Rewards { id: String, addr: String },
+    /// What approving the work would pay right now. Returns PayoutsResponse
+    PreviewPayouts { id: String },
+    /// Stake, key and encrypted work url of addr. Returns PositionResponse
+    Position { id: String, addr: String },
+    /// Deposits waiting for a slot, in promotion order. Returns WaitlistResponse
+    Waitlist { id: String },
+    /// Stake the next new staker needs. Returns QuoteResponse
+    Quote { id: String },
+    /// Returns CommissionResponse. The deliverable url is only shown
+    /// when addr is the client, the artist or the arbiter
+    Commission { id: String, addr: Option<String> },
+    /// Active market listings by listing id. Returns ListingsResponse
+    Listings {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -127,14 +475,49 @@ pub struct DetailsResponse {
     pub work_title: String,
     pub work_desc: String,
     pub work_url: String,
+    pub work_url_hash: String,
+    pub work_url_revealed: bool,
     pub start_time: Option<u64>,
-    pub account_min_stake_amount: u64,
-    pub stake_amount: u64,
+    pub account_min_stake_amount: Uint128,
+    pub stake_amount: Uint128,
     pub cw20_balance: Vec<Cw20Coin>,
+    pub reward_pool: Vec<Cw20Coin>,
+    /// Deposit sent with create, not part of the stake
+    pub client_bond_native: Vec<Coin>,
+    pub client_bond: Vec<Cw20Coin>,
+    pub arbiter: Option<String>,
+    pub collaborators: Vec<CollaboratorResponse>,
+    pub stake_token: String,
+    pub stake_symbol: String,
+    pub stake_decimals: u8,
+    /// stake_amount scaled by stake_decimals, e.g. "12.5"
+    pub stake_amount_display: String,
+    pub account_min_stake_amount_display: String,
     // pub account_info: Vec<AccountInfo>,
     pub account_info: String,
     pub state: u8,
+    /// Bond slashed or withdrawn, the stakers can only refund
+    pub closed: bool,
+    pub allowlist: Option<Allowlist>,
+    pub group: Option<GroupGate>,
+    pub max_stake_per_address: Option<Uint128>,
+    pub max_stakers: Option<u32>,
+    pub pricing: Option<BondingCurve>,
+    pub stakers: u32,
+    /// Stakers that can still join, None without max_stakers
+    pub remaining_stakers: Option<u32>,
+    /// Deposits queued until a staker slot frees up
+    pub waitlisted: u32,
+    /// What addr from the query can still stake, None without max_stake_per_address
+    pub my_remaining_stake: Option<Uint128>,
+    /// Whether addr from the query is on an address allowlist
+    pub my_allowlisted: bool,
+    /// Its url is empty for an addr that can not see work_url
+    pub deliverable: Option<Deliverable>,
+    /// Stake behind the flags on the deliverable
+    pub flagged_stake: Uint128,
     pub my_staked: String,
+    pub my_staked_display: String,
     pub expired: bool,
     pub timestamp: String,
     pub image_url: String
@@ -147,3 +530,179 @@ pub struct IsAdminResponse {
     pub isadmin: bool,
 }
 
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PauseResponse {
+    pub create: bool,
+    pub top_up: bool,
+    pub receive: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct WorkHistoryResponse {
+    pub id: String,
+    pub edits: Vec<WorkEdit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenResponse {
+    pub address: String,
+    pub min_stake: Uint128,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokensResponse {
+    pub tokens: Vec<TokenResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ClaimableResponse {
+    pub native: Vec<Coin>,
+    pub cw20: Vec<Cw20Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UnbondingClaimResponse {
+    /// escrow id the refund came from
+    pub id: String,
+    pub native: Vec<Coin>,
+    pub cw20: Vec<Cw20Coin>,
+    pub release_at: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UnbondingResponse {
+    /// Still locked
+    pub pending: Vec<UnbondingClaimResponse>,
+    /// Withdrawable with Claim {}
+    pub matured: Vec<UnbondingClaimResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingFeeResponse {
+    /// None when no fee is configured
+    pub fee: Option<ListingFeeMsg>,
+    /// Whether addr from the query is exempt, false without addr
+    pub exempt: bool,
+    /// Creates addr already paid the fee for, 0 without addr
+    pub prepaid: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SplitResponse {
+    pub default: RevenueSplitMsg,
+    pub limits: SplitLimits,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Payout {
+    /// client, manager, staker or extra
+    pub role: String,
+    /// Empty for the manager share while no manager or manager group admin
+    /// is set, it then goes to whoever does the manager approve
+    pub address: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PayoutsResponse {
+    pub id: String,
+    pub token: String,
+    pub payouts: Vec<Payout>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CollaboratorResponse {
+    pub member: String,
+    pub payout: String,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardsResponse {
+    pub id: String,
+    pub reward_pool: Vec<Cw20Coin>,
+    /// addr's share of the pool. Once settled it is part of Claimable
+    pub share: Vec<Cw20Coin>,
+    pub settled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EmissionResponse {
+    pub reward_token: String,
+    pub stake_token: String,
+    pub rate: Uint128,
+    pub period_finish: u64,
+    pub total_staked: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingEmissionResponse {
+    pub reward_token: String,
+    pub staked: Uint128,
+    pub pending: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AccessPassResponse {
+    pub nft_contract: Option<String>,
+    /// None until the work reached its goal with addr staked
+    pub token_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CommissionResponse {
+    pub id: String,
+    pub client: String,
+    pub artist: String,
+    pub arbiter: Option<String>,
+    pub work_title: String,
+    pub work_desc: String,
+    pub native_balance: Vec<Coin>,
+    pub cw20_balance: Vec<Cw20Coin>,
+    pub submit_deadline: u64,
+    pub review_period: u64,
+    pub deliverable: Option<Deliverable>,
+    pub status: CommissionStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingResponse {
+    pub listing_id: u64,
+    pub id: String,
+    pub seller: String,
+    pub amount: Uint128,
+    pub price: Uint128,
+    pub denom: Denom,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingsResponse {
+    pub listings: Vec<ListingResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PositionResponse {
+    pub id: String,
+    pub addr: String,
+    pub staked: Uint128,
+    pub pubkey: Option<Binary>,
+    /// Work url encrypted to pubkey, once the client uploaded it
+    pub encrypted_url: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct WaitlistResponse {
+    pub entries: Vec<WaitlistEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct QuoteResponse {
+    pub id: String,
+    /// Slots already taken, the quote is for the next one
+    pub slots: u32,
+    pub price: Uint128,
+    pub price_display: String,
+    pub pricing: Option<BondingCurve>,
+}
@@ -0,0 +1,1043 @@
+use cosmwasm_std::{coins, to_binary, Addr, Binary, Empty, StdError, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Denom};
+use cw4::Member;
+use cw721::{NftInfoResponse, OwnerOfResponse};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use serde::Serialize;
+
+use crate::msg::{
+    AccessPassResponse, ClaimableResponse, CollaboratorMsg, CommissionResponse, ConstantMsg, CreateCommissionMsg, CreateMsg, DetailsAllResponse, IsAdminResponse, EmissionResponse, EncryptedUrlMsg, ExecuteMsg, GroupGateMsg,
+    InstantiateMsg, ListingsResponse, PayoutsResponse, PositionResponse, PendingEmissionResponse, QueryMsg, ReceiveMsg, RevenueSplitMsg, SplitShareMsg, TopUpMsg, WaitlistResponse,
+};
+use crate::state::{AccessPassMetadata, PENDING_TRANSFERS};
+use crate::ContractError;
+
+const CLIENT: &str = "client";
+const MANAGER: &str = "manager";
+const STAKER1: &str = "staker1";
+const STAKER2: &str = "staker2";
+const GROUP_ADMIN: &str = "groupadmin";
+const BOND_DENOM: &str = "ujuno";
+const WORK_URL_HASH: &str = "a0b1c2d3e4f5a0b1c2d3e4f5a0b1c2d3e4f5a0b1c2d3e4f5a0b1c2d3e4f5a0b1";
+
+fn contract_doodle() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply);
+    Box::new(contract)
+}
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn contract_cw4_group() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw4_group::contract::execute,
+        cw4_group::contract::instantiate,
+        cw4_group::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// cw20 whose transfers to STAKER1 always fail, like a frozen account
+fn contract_frozen_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |deps, env, info, msg: Cw20ExecuteMsg| match &msg {
+            Cw20ExecuteMsg::Transfer { recipient, .. } if recipient == STAKER1 => {
+                Err(cw20_base::ContractError::Std(StdError::generic_err("account frozen")))
+            }
+            _ => cw20_base::contract::execute(deps, env, info, msg),
+        },
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+type AccessPassNft<'a> = cw721_base::Cw721Contract<'a, AccessPassMetadata, Empty>;
+
+fn contract_access_pass() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |deps, env, info, msg: cw721_base::ExecuteMsg<AccessPassMetadata>| {
+            AccessPassNft::default().execute(deps, env, info, msg)
+        },
+        |deps, env, info, msg: cw721_base::InstantiateMsg| {
+            AccessPassNft::default().instantiate(deps, env, info, msg)
+        },
+        |deps, env, msg: cw721_base::QueryMsg| AccessPassNft::default().query(deps, env, msg),
+    );
+    Box::new(contract)
+}
+
+struct Suite {
+    app: App,
+    doodle: Addr,
+    crew: Addr,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(CLIENT), coins(10_000, BOND_DENOM))
+                .unwrap();
+        });
+
+        let cw20_id = app.store_code(contract_cw20());
+        let crew = app
+            .instantiate_contract(
+                cw20_id,
+                Addr::unchecked(MANAGER),
+                &cw20_base::msg::InstantiateMsg {
+                    name: "Crew Token".to_string(),
+                    symbol: "CREW".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![
+                        Cw20Coin { address: STAKER1.to_string(), amount: Uint128::new(10_000) },
+                        Cw20Coin { address: STAKER2.to_string(), amount: Uint128::new(10_000) },
+                        Cw20Coin { address: GROUP_ADMIN.to_string(), amount: Uint128::new(10_000) },
+                    ],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "crew",
+                None,
+            )
+            .unwrap();
+
+        let doodle_id = app.store_code(contract_doodle());
+        let doodle = app
+            .instantiate_contract(
+                doodle_id,
+                Addr::unchecked(MANAGER),
+                &InstantiateMsg { crew_address: crew.to_string() },
+                &[],
+                "doodle",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            Addr::unchecked(MANAGER),
+            doodle.clone(),
+            &ExecuteMsg::SetConstant(constant_msg(MANAGER)),
+            &[],
+        )
+        .unwrap();
+
+        Suite { app, doodle, crew }
+    }
+
+    fn execute<T: Serialize + std::fmt::Debug>(&mut self, sender: &str, msg: &T) -> Result<AppResponse, String> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.doodle.clone(), msg, &[])
+            .map_err(|e| e.root_cause().to_string())
+    }
+
+    fn cw20_token(&mut self, code: Box<dyn Contract<Empty>>, symbol: &str) -> Addr {
+        let code_id = self.app.store_code(code);
+        self.app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked(MANAGER),
+                &cw20_base::msg::InstantiateMsg {
+                    name: format!("{} Token", symbol),
+                    symbol: symbol.to_string(),
+                    decimals: 6,
+                    initial_balances: vec![
+                        Cw20Coin { address: STAKER1.to_string(), amount: Uint128::new(10_000) },
+                        Cw20Coin { address: STAKER2.to_string(), amount: Uint128::new(10_000) },
+                    ],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                symbol,
+                None,
+            )
+            .unwrap()
+    }
+
+    fn cw20_balance(&self, token: &Addr, addr: &str) -> u128 {
+        let balance: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(token, &Cw20QueryMsg::Balance { address: addr.to_string() })
+            .unwrap();
+        balance.balance.u128()
+    }
+
+    fn claimable(&self, addr: &str) -> ClaimableResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.doodle, &QueryMsg::Claimable { addr: addr.to_string() })
+            .unwrap()
+    }
+
+    fn send(&mut self, staker: &str, token: &Addr, amount: u128, msg: &ReceiveMsg) -> Result<AppResponse, String> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(staker),
+                token.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: self.doodle.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(msg).unwrap(),
+                },
+                &[],
+            )
+            .map_err(|e| e.root_cause().to_string())
+    }
+
+    fn create(&mut self, msg: CreateMsg) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(CLIENT),
+                self.doodle.clone(),
+                &ExecuteMsg::Create(msg),
+                &coins(100, BOND_DENOM),
+            )
+            .unwrap();
+    }
+
+    fn top_up(&mut self, staker: &str, id: &str, amount: u128) -> Result<(), String> {
+        let crew = self.crew.clone();
+        self.top_up_in(staker, &crew, id, amount)
+    }
+
+    fn top_up_in(&mut self, staker: &str, token: &Addr, id: &str, amount: u128) -> Result<(), String> {
+        let msg = ReceiveMsg::TopUp(TopUpMsg {
+            id: id.to_string(),
+            start_time: 0,
+            end_time: 0,
+            proof: None,
+        });
+        self.send(staker, token, amount, &msg).map(|_| ())
+    }
+
+    fn access_pass(&self, id: &str, addr: &str) -> AccessPassResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                &self.doodle,
+                &QueryMsg::AccessPass { id: id.to_string(), addr: addr.to_string() },
+            )
+            .unwrap()
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.time = block.time.plus_seconds(seconds);
+            block.height += 1;
+        });
+    }
+}
+
+fn constant_msg(manager_addr: &str) -> ConstantMsg {
+    ConstantMsg {
+        manager_addr: manager_addr.to_string(),
+        min_stake: "10".to_string(),
+        rate_client: "10".to_string(),
+        rate_manager: "10".to_string(),
+        unbonding_period: None,
+        royalty_bps: None,
+        review_window: None,
+        delivery_timeout: None,
+        flag_threshold_bps: None,
+    }
+}
+
+fn create_msg(id: &str, start_time: u64) -> CreateMsg {
+    CreateMsg {
+        id: id.to_string(),
+        client: CLIENT.to_string(),
+        arbiter: None,
+        stake_token: None,
+        work_title: "Mural".to_string(),
+        work_desc: "A mural".to_string(),
+        work_url_hash: WORK_URL_HASH.to_string(),
+        start_time: Some(start_time),
+        account_min_stake_amount: Uint128::zero(),
+        stake_amount: Uint128::new(100),
+        image_url: String::new(),
+        split: None,
+        collaborators: None,
+        unbonding_period: None,
+        allowlist: None,
+        group: None,
+        max_stake_per_address: None,
+        max_stakers: None,
+        pricing: None,
+    }
+}
+
+fn setup_access_pass(suite: &mut Suite) -> Addr {
+    let nft_id = suite.app.store_code(contract_access_pass());
+    let nft = suite
+        .app
+        .instantiate_contract(
+            nft_id,
+            Addr::unchecked(MANAGER),
+            &cw721_base::InstantiateMsg {
+                name: "Access Pass".to_string(),
+                symbol: "PASS".to_string(),
+                minter: suite.doodle.to_string(),
+            },
+            &[],
+            "access pass",
+            None,
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(MANAGER),
+            suite.doodle.clone(),
+            &ExecuteMsg::SetAccessPassNft { address: Some(nft.to_string()) },
+            &[],
+        )
+        .unwrap();
+    nft
+}
+
+#[test]
+fn access_passes_minted_on_funding() {
+    let mut suite = Suite::new();
+    let nft = setup_access_pass(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+    assert_eq!(suite.access_pass("mural", STAKER1).token_id, None);
+
+    suite.top_up(STAKER2, "mural", 40).unwrap();
+    for (staker, amount) in [(STAKER1, 60u128), (STAKER2, 40)] {
+        let pass = suite.access_pass("mural", staker);
+        assert_eq!(pass.nft_contract, Some(nft.to_string()));
+        let token_id = pass.token_id.unwrap();
+
+        let owner: OwnerOfResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &nft,
+                &cw721_base::QueryMsg::OwnerOf { token_id: token_id.clone(), include_expired: None },
+            )
+            .unwrap();
+        assert_eq!(owner.owner, staker);
+
+        let info: NftInfoResponse<AccessPassMetadata> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&nft, &cw721_base::QueryMsg::NftInfo { token_id })
+            .unwrap();
+        assert_eq!(
+            info.extension,
+            AccessPassMetadata { work_id: "mural".to_string(), amount: Uint128::new(amount) }
+        );
+    }
+}
+
+fn pass_owner(suite: &Suite, nft: &Addr, token_id: &str) -> Option<String> {
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart::<OwnerOfResponse>(
+            nft,
+            &cw721_base::QueryMsg::OwnerOf { token_id: token_id.to_string(), include_expired: None },
+        )
+        .ok()
+        .map(|res| res.owner)
+}
+
+fn approve_passes(suite: &mut Suite, nft: &Addr, owner: &str) {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(owner),
+            nft.clone(),
+            &cw721_base::ExecuteMsg::<AccessPassMetadata>::ApproveAll {
+                operator: suite.doodle.to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+#[test]
+fn access_pass_follows_the_position() {
+    let mut suite = Suite::new();
+    let nft = setup_access_pass(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+    suite.top_up(STAKER2, "mural", 40).unwrap();
+    let transfer = |recipient: &str, amount: u128| ExecuteMsg::TransferPosition {
+        id: "mural".to_string(),
+        recipient: recipient.to_string(),
+        amount: Uint128::new(amount),
+    };
+
+    // part of a position: both ends hold a pass
+    let staker1_pass = suite.access_pass("mural", STAKER1).token_id.unwrap();
+    suite.execute(STAKER1, &transfer("fan", 20)).unwrap();
+    assert_eq!(pass_owner(&suite, &nft, &staker1_pass), Some(STAKER1.to_string()));
+    let fan_pass = suite.access_pass("mural", "fan").token_id.unwrap();
+    assert_eq!(pass_owner(&suite, &nft, &fan_pass), Some("fan".to_string()));
+
+    // all of it: the pass goes along, once the contract may move it
+    let staker2_pass = suite.access_pass("mural", STAKER2).token_id.unwrap();
+    suite.execute(STAKER2, &transfer("collector", 40)).unwrap_err();
+    approve_passes(&mut suite, &nft, STAKER2);
+    suite.execute(STAKER2, &transfer("collector", 40)).unwrap();
+    assert_eq!(suite.access_pass("mural", STAKER2).token_id, None);
+    assert_eq!(suite.access_pass("mural", "collector").token_id, Some(staker2_pass.clone()));
+    assert_eq!(pass_owner(&suite, &nft, &staker2_pass), Some("collector".to_string()));
+
+    // to someone holding a pass already, the leaving one is burned
+    approve_passes(&mut suite, &nft, "fan");
+    suite.execute("fan", &transfer(STAKER1, 20)).unwrap();
+    assert_eq!(suite.access_pass("mural", "fan").token_id, None);
+    assert_eq!(pass_owner(&suite, &nft, &fan_pass), None);
+    assert_eq!(suite.access_pass("mural", STAKER1).token_id, Some(staker1_pass));
+}
+
+#[test]
+fn bought_position_takes_the_pass() {
+    let mut suite = Suite::new();
+    let crew = suite.crew.clone();
+    let nft = setup_access_pass(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+    suite.top_up(STAKER2, "mural", 40).unwrap();
+    let list = |amount: u128, price: u128| ExecuteMsg::ListPosition {
+        id: "mural".to_string(),
+        amount: Uint128::new(amount),
+        price: Uint128::new(price),
+        denom: Denom::Cw20(crew.clone()),
+    };
+
+    let err = suite.execute(STAKER2, &list(40, 0)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPrice {}.to_string());
+    let pass = suite.access_pass("mural", STAKER2).token_id.unwrap();
+    approve_passes(&mut suite, &nft, STAKER2);
+    suite.execute(STAKER2, &list(40, 50)).unwrap();
+    suite.send(GROUP_ADMIN, &crew, 50, &ReceiveMsg::BuyPosition { listing_id: 1 }).unwrap();
+    assert_eq!(suite.access_pass("mural", STAKER2).token_id, None);
+    assert_eq!(suite.access_pass("mural", GROUP_ADMIN).token_id, Some(pass.clone()));
+    assert_eq!(pass_owner(&suite, &nft, &pass), Some(GROUP_ADMIN.to_string()));
+
+    // listings left open at approval are dropped
+    suite.execute(STAKER1, &list(10, 20)).unwrap();
+    suite.advance(200);
+    let submit = ExecuteMsg::SubmitDeliverable {
+        id: "mural".to_string(),
+        url: "https://example.com/mural".to_string(),
+        content_hash: WORK_URL_HASH.to_string(),
+    };
+    suite.execute(CLIENT, &submit).unwrap();
+    suite.advance(259_200);
+    suite.execute(CLIENT, &ExecuteMsg::Approve { id: "mural".to_string() }).unwrap();
+    let listings: ListingsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.doodle, &QueryMsg::Listings { start_after: None, limit: None })
+        .unwrap();
+    assert!(listings.listings.is_empty());
+}
+
+#[test]
+fn access_passes_reset_when_id_reused() {
+    let mut suite = Suite::new();
+    setup_access_pass(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 100).unwrap();
+    let first = suite.access_pass("mural", STAKER1).token_id.unwrap();
+
+    // deliver, let the review window pass and settle both approvals
+    suite.advance(200);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(CLIENT),
+            suite.doodle.clone(),
+            &ExecuteMsg::SubmitDeliverable {
+                id: "mural".to_string(),
+                url: "https://example.com/mural".to_string(),
+                content_hash: WORK_URL_HASH.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.advance(259_200);
+    for approver in [CLIENT, MANAGER] {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(approver),
+                suite.doodle.clone(),
+                &ExecuteMsg::Approve { id: "mural".to_string() },
+                &[],
+            )
+            .unwrap();
+    }
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(MANAGER),
+            suite.doodle.clone(),
+            &ExecuteMsg::Remove { id: "mural".to_string() },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.access_pass("mural", STAKER1).token_id, None);
+
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 100).unwrap();
+    let second = suite.access_pass("mural", STAKER1).token_id.unwrap();
+    assert_ne!(first, second);
+}
+
+fn setup_group(suite: &mut Suite) -> Addr {
+    let group_id = suite.app.store_code(contract_cw4_group());
+    suite
+        .app
+        .instantiate_contract(
+            group_id,
+            Addr::unchecked(GROUP_ADMIN),
+            &cw4_group::msg::InstantiateMsg {
+                admin: Some(GROUP_ADMIN.to_string()),
+                members: vec![
+                    Member { addr: STAKER1.to_string(), weight: 2 },
+                    Member { addr: STAKER2.to_string(), weight: 1 },
+                ],
+            },
+            &[],
+            "group",
+            None,
+        )
+        .unwrap()
+}
+
+fn group_msg(id: &str, start_time: u64, group: &Addr, stake_per_weight: Option<u128>) -> CreateMsg {
+    CreateMsg {
+        group: Some(GroupGateMsg {
+            address: group.to_string(),
+            stake_per_weight: stake_per_weight.map(Uint128::new),
+        }),
+        ..create_msg(id, start_time)
+    }
+}
+
+#[test]
+fn group_members_only_top_up() {
+    let mut suite = Suite::new();
+    let group = setup_group(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(group_msg("mural", start, &group, None));
+
+    let err = suite.top_up(GROUP_ADMIN, "mural", 50).unwrap_err();
+    assert_eq!(err, ContractError::NotGroupMember {}.to_string());
+    suite.top_up(STAKER1, "mural", 50).unwrap();
+    suite.top_up(STAKER2, "mural", 20).unwrap();
+}
+
+#[test]
+fn group_caps_stake_by_weight() {
+    let mut suite = Suite::new();
+    let group = setup_group(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(group_msg("mural", start, &group, Some(20)));
+
+    // weight 2 allows 40, weight 1 allows 20
+    suite.top_up(STAKER1, "mural", 30).unwrap();
+    let err = suite.top_up(STAKER1, "mural", 15).unwrap_err();
+    assert_eq!(err, ContractError::GroupCapExceeded {}.to_string());
+    suite.top_up(STAKER1, "mural", 10).unwrap();
+
+    let err = suite.top_up(STAKER2, "mural", 21).unwrap_err();
+    assert_eq!(err, ContractError::GroupCapExceeded {}.to_string());
+    suite.top_up(STAKER2, "mural", 20).unwrap();
+}
+
+#[test]
+fn group_admin_acts_as_manager() {
+    let mut suite = Suite::new();
+    let group = setup_group(&mut suite);
+    let set_denom = ExecuteMsg::SetMarketDenom { denom: BOND_DENOM.to_string(), enabled: true };
+
+    let err = suite
+        .app
+        .execute_contract(Addr::unchecked(GROUP_ADMIN), suite.doodle.clone(), &set_denom, &[])
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), ContractError::Unauthorized {}.to_string());
+
+    // only the configured manager hands out the group
+    let set_group = ExecuteMsg::SetManagerGroup { group: Some(group.to_string()) };
+    let err = suite
+        .app
+        .execute_contract(Addr::unchecked(STAKER1), suite.doodle.clone(), &set_group, &[])
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), ContractError::Unauthorized {}.to_string());
+    suite
+        .app
+        .execute_contract(Addr::unchecked(MANAGER), suite.doodle.clone(), &set_group, &[])
+        .unwrap();
+
+    suite
+        .app
+        .execute_contract(Addr::unchecked(GROUP_ADMIN), suite.doodle.clone(), &set_denom, &[])
+        .unwrap();
+    let err = suite
+        .app
+        .execute_contract(Addr::unchecked(STAKER1), suite.doodle.clone(), &set_denom, &[])
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), ContractError::Unauthorized {}.to_string());
+}
+
+#[test]
+fn manager_group_alone_keeps_constants_closed() {
+    let mut suite = Suite::new();
+    let group = setup_group(&mut suite);
+    suite.execute(MANAGER, &ExecuteMsg::SetManagerGroup { group: Some(group.to_string()) }).unwrap();
+    suite.execute(MANAGER, &ExecuteMsg::SetConstant(constant_msg(""))).unwrap();
+
+    let err = suite.execute(STAKER1, &ExecuteMsg::SetConstant(constant_msg(STAKER1))).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {}.to_string());
+    for (addr, admin) in [(STAKER1, false), (GROUP_ADMIN, true)] {
+        let res: IsAdminResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&suite.doodle, &QueryMsg::IsAdmin { addr: addr.to_string() })
+            .unwrap();
+        assert_eq!(res.isadmin, admin);
+    }
+
+    // details_all keeps the admin view for the group admin
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+    for (addr, admin) in [(STAKER2, false), (GROUP_ADMIN, true)] {
+        let details: DetailsAllResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&suite.doodle, &QueryMsg::DetailsAll { addr: addr.to_string() })
+            .unwrap();
+        assert_eq!(!details.escrows[0].cw20_balance.is_empty(), admin);
+        assert_eq!(!details.escrows[0].account_info.is_empty(), admin);
+    }
+    suite.execute(GROUP_ADMIN, &ExecuteMsg::SetConstant(constant_msg(GROUP_ADMIN))).unwrap();
+}
+
+#[test]
+fn failed_transfer_becomes_claimable() {
+    let mut suite = Suite::new();
+    let frozen = suite.cw20_token(contract_frozen_cw20(), "FRZN");
+    suite
+        .execute(
+            MANAGER,
+            &ExecuteMsg::AddToken { address: frozen.to_string(), min_stake: Uint128::zero(), enabled: true },
+        )
+        .unwrap();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(CreateMsg { stake_token: Some(frozen.to_string()), ..create_msg("mural", start) });
+    suite.top_up_in(STAKER1, &frozen, "mural", 30).unwrap();
+    suite.top_up_in(STAKER2, &frozen, "mural", 20).unwrap();
+
+    // the goal is missed, both take their stake back
+    suite.advance(200);
+    for staker in [STAKER1, STAKER2] {
+        suite.execute(staker, &ExecuteMsg::Refund { id: "mural".to_string() }).unwrap();
+    }
+
+    // the frozen transfer is kept for a later claim
+    assert_eq!(suite.cw20_balance(&frozen, STAKER1), 9_970);
+    let claimable = suite.claimable(STAKER1);
+    assert_eq!(claimable.cw20, vec![Cw20Coin { address: frozen.to_string(), amount: Uint128::new(30) }]);
+    assert!(claimable.native.is_empty());
+
+    // the delivered one is paid and forgotten
+    assert_eq!(suite.cw20_balance(&frozen, STAKER2), 10_000);
+    assert_eq!(suite.claimable(STAKER2), ClaimableResponse { native: vec![], cw20: vec![] });
+    for id in 1..=2u64 {
+        let pending = PENDING_TRANSFERS.query(&suite.app.wrap(), suite.doodle.clone(), id).unwrap();
+        assert_eq!(pending, None);
+    }
+}
+
+#[test]
+fn slash_closes_the_work() {
+    let mut suite = Suite::new();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+    suite.top_up(STAKER2, "mural", 40).unwrap();
+
+    let slash = ExecuteMsg::SlashBond { id: "mural".to_string() };
+    let err = suite.execute(MANAGER, &slash).unwrap_err();
+    assert_eq!(err, ContractError::DeliveryNotMissed {}.to_string());
+    suite.advance(2_592_000);
+    suite.execute(MANAGER, &slash).unwrap();
+    assert_eq!(suite.claimable(STAKER1).native, coins(60, BOND_DENOM));
+    assert_eq!(suite.claimable(STAKER2).native, coins(40, BOND_DENOM));
+
+    let submit = ExecuteMsg::SubmitDeliverable {
+        id: "mural".to_string(),
+        url: "https://example.com/mural".to_string(),
+        content_hash: WORK_URL_HASH.to_string(),
+    };
+    let err = suite.execute(CLIENT, &submit).unwrap_err();
+    assert_eq!(err, ContractError::WorkClosed {}.to_string());
+    let err = suite.execute(CLIENT, &ExecuteMsg::Approve { id: "mural".to_string() }).unwrap_err();
+    assert_eq!(err, ContractError::WorkClosed {}.to_string());
+
+    for staker in [STAKER1, STAKER2] {
+        suite.execute(staker, &ExecuteMsg::Refund { id: "mural".to_string() }).unwrap();
+    }
+    assert_eq!(suite.cw20_balance(&suite.crew.clone(), STAKER1), 10_000);
+    suite.execute(MANAGER, &ExecuteMsg::Remove { id: "mural".to_string() }).unwrap();
+}
+
+#[test]
+fn bond_withdrawn_when_goal_missed() {
+    let mut suite = Suite::new();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+
+    let withdraw = ExecuteMsg::WithdrawBond { id: "mural".to_string() };
+    let err = suite.execute(CLIENT, &withdraw).unwrap_err();
+    assert_eq!(err, ContractError::WorkNotExpired {}.to_string());
+    suite.advance(200);
+    let err = suite.execute(STAKER1, &withdraw).unwrap_err();
+    assert_eq!(err, ContractError::NotClient {}.to_string());
+    suite.execute(CLIENT, &withdraw).unwrap();
+    let bond = suite.app.wrap().query_balance(CLIENT, BOND_DENOM).unwrap();
+    assert_eq!(bond.amount.u128(), 10_000);
+
+    // nobody can join any more, the staker still gets out
+    let err = suite.top_up(STAKER2, "mural", 40).unwrap_err();
+    assert_eq!(err, ContractError::WorkClosed {}.to_string());
+    suite.execute(STAKER1, &ExecuteMsg::Refund { id: "mural".to_string() }).unwrap();
+    assert_eq!(suite.cw20_balance(&suite.crew.clone(), STAKER1), 10_000);
+}
+
+#[test]
+fn reward_pool_follows_the_bond() {
+    let mut suite = Suite::new();
+    let crew = suite.crew.clone();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("missed", start));
+    suite.create(create_msg("slashed", start + 10_000));
+    for id in ["missed", "slashed"] {
+        suite.send(STAKER2, &crew, 30, &ReceiveMsg::FundRewards { id: id.to_string() }).unwrap();
+    }
+
+    // a missed goal gives the pool back with the bond
+    suite.top_up(STAKER1, "missed", 10).unwrap();
+    suite.advance(200);
+    suite.execute(CLIENT, &ExecuteMsg::WithdrawBond { id: "missed".to_string() }).unwrap();
+    assert_eq!(suite.cw20_balance(&crew, CLIENT), 30);
+
+    // a slash shares it with the stakers
+    suite.top_up(STAKER1, "slashed", 100).unwrap();
+    suite.advance(2_592_000);
+    suite.execute(MANAGER, &ExecuteMsg::SlashBond { id: "slashed".to_string() }).unwrap();
+    assert_eq!(
+        suite.claimable(STAKER1).cw20,
+        vec![Cw20Coin { address: crew.to_string(), amount: Uint128::new(30) }]
+    );
+}
+
+#[test]
+fn preview_matches_approval_payouts() {
+    let mut suite = Suite::new();
+    let crew = suite.crew.clone();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(CreateMsg {
+        stake_amount: Uint128::new(101),
+        split: Some(RevenueSplitMsg {
+            client_bps: 5000,
+            manager_bps: 1000,
+            stakers_bps: 3000,
+            extra_shares: vec![SplitShareMsg { address: "curator".to_string(), bps: 1000 }],
+        }),
+        collaborators: Some(vec![
+            CollaboratorMsg { address: CLIENT.to_string(), weight: 1 },
+            CollaboratorMsg { address: "painter".to_string(), weight: 2 },
+        ]),
+        ..create_msg("mural", start)
+    });
+    suite.top_up(STAKER1, "mural", 37).unwrap();
+    suite.top_up(STAKER2, "mural", 64).unwrap();
+
+    let preview: PayoutsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.doodle, &QueryMsg::PreviewPayouts { id: "mural".to_string() })
+        .unwrap();
+    let total: u128 = preview.payouts.iter().map(|p| p.amount.u128()).sum();
+    assert_eq!(total, 101);
+    let manager = preview.payouts.iter().find(|p| p.role == "manager").unwrap();
+    assert_eq!(manager.address, MANAGER);
+
+    suite.advance(200);
+    let submit = ExecuteMsg::SubmitDeliverable {
+        id: "mural".to_string(),
+        url: "https://example.com/mural".to_string(),
+        content_hash: WORK_URL_HASH.to_string(),
+    };
+    suite.execute(CLIENT, &submit).unwrap();
+    suite.advance(259_200);
+    suite.execute(CLIENT, &ExecuteMsg::Approve { id: "mural".to_string() }).unwrap();
+    suite.execute(MANAGER, &ExecuteMsg::Approve { id: "mural".to_string() }).unwrap();
+
+    let mut payees: Vec<String> = preview.payouts.iter().map(|p| p.address.clone()).collect();
+    payees.dedup();
+    assert_eq!(payees.len(), 6);
+    for payee in payees {
+        let expected: u128 = preview
+            .payouts
+            .iter()
+            .filter(|p| p.address == payee)
+            .map(|p| p.amount.u128())
+            .sum();
+        let credited = suite.claimable(&payee).cw20;
+        assert_eq!(credited, vec![Cw20Coin { address: crew.to_string(), amount: Uint128::new(expected) }]);
+    }
+}
+
+#[test]
+fn emission_shared_by_stake_over_time() {
+    let mut suite = Suite::new();
+    let crew = suite.crew.clone();
+    let start = suite.app.block_info().time.seconds();
+    suite.create(CreateMsg { stake_amount: Uint128::new(40), ..create_msg("mural", start + 60) });
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(GROUP_ADMIN),
+            crew.clone(),
+            &Cw20ExecuteMsg::Transfer { recipient: MANAGER.to_string(), amount: Uint128::new(1_000) },
+            &[],
+        )
+        .unwrap();
+    suite.send(MANAGER, &crew, 1_000, &ReceiveMsg::FundEmission { duration: 100 }).unwrap();
+
+    let pending = |suite: &Suite, addr: &str| -> u128 {
+        let res: PendingEmissionResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&suite.doodle, &QueryMsg::PendingEmission { addr: addr.to_string() })
+            .unwrap();
+        res.pending.u128()
+    };
+
+    // nothing is staked for the first 50 seconds, so the stream is held
+    suite.advance(50);
+    suite.top_up(STAKER1, "mural", 25).unwrap();
+    let emission: EmissionResponse =
+        suite.app.wrap().query_wasm_smart(&suite.doodle, &QueryMsg::Emission {}).unwrap();
+    assert_eq!(emission.rate.u128(), 10);
+    assert_eq!(emission.period_finish, start + 150);
+
+    // staker1 alone earns the full rate, then shares it 25:15
+    suite.advance(20);
+    assert_eq!(pending(&suite, STAKER1), 200);
+    suite.top_up(STAKER2, "mural", 15).unwrap();
+    suite.advance(10);
+    let submit = ExecuteMsg::SubmitDeliverable {
+        id: "mural".to_string(),
+        url: "https://example.com/mural".to_string(),
+        content_hash: WORK_URL_HASH.to_string(),
+    };
+    suite.execute(CLIENT, &submit).unwrap();
+    suite.advance(259_200);
+    assert_eq!(pending(&suite, STAKER1), 700);
+    assert_eq!(pending(&suite, STAKER2), 300);
+
+    // approval takes the stakes out, what was earned stays claimable
+    suite.execute(CLIENT, &ExecuteMsg::Approve { id: "mural".to_string() }).unwrap();
+    let emission: EmissionResponse =
+        suite.app.wrap().query_wasm_smart(&suite.doodle, &QueryMsg::Emission {}).unwrap();
+    assert!(emission.total_staked.is_zero());
+    suite.advance(100);
+    for (staker, earned) in [(STAKER1, 700), (STAKER2, 300)] {
+        assert_eq!(pending(&suite, staker), earned);
+        let before = suite.cw20_balance(&crew, staker);
+        let payouts: u128 = suite.claimable(staker).cw20.iter().map(|c| c.amount.u128()).sum();
+        suite.execute(staker, &ExecuteMsg::Claim {}).unwrap();
+        assert_eq!(suite.cw20_balance(&crew, staker), before + payouts + earned);
+        assert_eq!(pending(&suite, staker), 0);
+    }
+}
+
+#[test]
+fn commission_rejection_rules() {
+    let mut suite = Suite::new();
+    let create = |suite: &mut Suite, id: &str, arbiter: Option<&str>| {
+        let msg = ExecuteMsg::CreateCommission(CreateCommissionMsg {
+            id: id.to_string(),
+            artist: "artist".to_string(),
+            arbiter: arbiter.map(|a| a.to_string()),
+            work_title: "Portrait".to_string(),
+            work_desc: String::new(),
+            submit_deadline: 100,
+            review_period: 100,
+        });
+        suite
+            .app
+            .execute_contract(Addr::unchecked(CLIENT), suite.doodle.clone(), &msg, &coins(100, BOND_DENOM))
+            .unwrap();
+    };
+    let submit = |id: &str| ExecuteMsg::SubmitCommission {
+        id: id.to_string(),
+        url: "https://example.com/portrait".to_string(),
+        content_hash: WORK_URL_HASH.to_string(),
+    };
+    let reject = |id: &str| ExecuteMsg::RejectCommission { id: id.to_string() };
+    let commission = |suite: &Suite, id: &str, addr: &str| -> CommissionResponse {
+        suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &suite.doodle,
+                &QueryMsg::Commission { id: id.to_string(), addr: Some(addr.to_string()) },
+            )
+            .unwrap()
+    };
+    create(&mut suite, "open", None);
+    create(&mut suite, "arbitrated", Some("arbiter"));
+
+    // the artist keeps the submission period
+    let err = suite.execute(CLIENT, &reject("open")).unwrap_err();
+    assert_eq!(err, ContractError::NotTimedOut {}.to_string());
+
+    // only the parties see what was submitted
+    suite.execute("artist", &submit("arbitrated")).unwrap();
+    for (viewer, url) in [(CLIENT, true), ("artist", true), ("arbiter", true), (STAKER1, false)] {
+        let deliverable = commission(&suite, "arbitrated", viewer).deliverable.unwrap();
+        assert_eq!(!deliverable.url.is_empty(), url);
+        assert_eq!(deliverable.content_hash, WORK_URL_HASH);
+    }
+
+    // with an arbiter the client can not turn down a submission
+    let err = suite.execute(CLIENT, &reject("arbitrated")).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {}.to_string());
+    suite.execute("arbiter", &reject("arbitrated")).unwrap();
+    assert_eq!(suite.app.wrap().query_balance(CLIENT, BOND_DENOM).unwrap().amount.u128(), 9_900);
+
+    suite.advance(101);
+    suite.execute(CLIENT, &reject("open")).unwrap();
+    assert_eq!(suite.app.wrap().query_balance(CLIENT, BOND_DENOM).unwrap().amount.u128(), 10_000);
+}
+
+#[test]
+fn delivered_url_redacted_like_work_url() {
+    let mut suite = Suite::new();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 100).unwrap();
+    suite.advance(200);
+    let submit = ExecuteMsg::SubmitDeliverable {
+        id: "mural".to_string(),
+        url: "https://example.com/mural".to_string(),
+        content_hash: WORK_URL_HASH.to_string(),
+    };
+    suite.execute(CLIENT, &submit).unwrap();
+
+    for (viewer, url) in [(STAKER1, "https://example.com/mural"), (STAKER2, "")] {
+        let details: DetailsAllResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&suite.doodle, &QueryMsg::DetailsAll { addr: viewer.to_string() })
+            .unwrap();
+        let deliverable = details.escrows[0].deliverable.clone().unwrap();
+        assert_eq!(deliverable.url, url);
+        assert_eq!(deliverable.content_hash, WORK_URL_HASH);
+    }
+
+    let err = suite
+        .execute(
+            MANAGER,
+            &ExecuteMsg::SetConstant(ConstantMsg {
+                flag_threshold_bps: Some("10001".to_string()),
+                ..constant_msg(MANAGER)
+            }),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFlagThreshold {}.to_string());
+}
+
+#[test]
+fn encrypted_urls_wait_for_every_key() {
+    let mut suite = Suite::new();
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(create_msg("mural", start));
+    suite.top_up(STAKER1, "mural", 60).unwrap();
+    suite.top_up(STAKER2, "mural", 40).unwrap();
+    let mut pubkey = vec![0x02u8];
+    pubkey.extend([7u8; 32]);
+    let register = ExecuteMsg::RegisterPubkey { id: "mural".to_string(), pubkey: Binary(pubkey) };
+    let upload = |stakers: &[&str]| ExecuteMsg::UploadEncryptedUrls {
+        id: "mural".to_string(),
+        blobs: stakers
+            .iter()
+            .map(|staker| EncryptedUrlMsg { staker: staker.to_string(), ciphertext: Binary(b"sealed".to_vec()) })
+            .collect(),
+    };
+
+    suite.execute(STAKER1, &register).unwrap();
+    let err = suite.execute(CLIENT, &upload(&[STAKER1])).unwrap_err();
+    assert_eq!(err, ContractError::NoPubkey { staker: STAKER2.to_string() }.to_string());
+
+    suite.execute(STAKER2, &register).unwrap();
+    let err = suite.execute(CLIENT, &upload(&[STAKER1])).unwrap_err();
+    assert_eq!(err, ContractError::MissingBlob { staker: STAKER2.to_string() }.to_string());
+    suite.execute(CLIENT, &upload(&[STAKER1, STAKER2])).unwrap();
+}
+
+#[test]
+fn waitlist_promotion_respects_group_cap() {
+    let mut suite = Suite::new();
+    let group = setup_group(&mut suite);
+    let start = suite.app.block_info().time.seconds() + 100;
+    suite.create(CreateMsg { max_stakers: Some(1), ..group_msg("mural", start, &group, Some(10)) });
+    suite.top_up(STAKER2, "mural", 10).unwrap();
+
+    // each deposit fits the cap of 20 on its own, not both together
+    suite.top_up(STAKER1, "mural", 20).unwrap();
+    suite.top_up(STAKER1, "mural", 20).unwrap();
+    suite.advance(200);
+    suite.execute(STAKER2, &ExecuteMsg::Refund { id: "mural".to_string() }).unwrap();
+
+    let position: PositionResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.doodle,
+            &QueryMsg::Position { id: "mural".to_string(), addr: STAKER1.to_string() },
+        )
+        .unwrap();
+    assert_eq!(position.staked.u128(), 20);
+    let waitlist: WaitlistResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.doodle, &QueryMsg::Waitlist { id: "mural".to_string() })
+        .unwrap();
+    assert_eq!(waitlist.entries.len(), 1);
+}
@@ -1,10 +1,15 @@
+use std::convert::TryFrom;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Coin, Env, Order, StdResult, Storage, Timestamp};
-use cw_storage_plus::Map;
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Env, Order, StdError, StdResult, Storage, Timestamp, Uint128, Uint256};
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+
+use cw20::{Balance, Cw20CoinVerified, Denom};
 
-use cw20::{Balance, Cw20CoinVerified};
+use crate::error::ContractError;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct GenericBalance {
@@ -20,11 +25,89 @@ pub struct GenericAccount {
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AccountInfo {
     pub addr: Addr,
-    pub amount: u128,
+    pub amount: Uint128,
     pub start_time: u64,
-    pub end_time: u64
+    pub end_time: u64,
+    /// Block time the contract recorded the stake at, 0 for entries written before it was kept
+    pub staked_at: u64
 }
 impl GenericAccount {
+    /// Reads the `;addr:amount:start_time:end_time:staked_at` list kept in Escrow.account_info.
+    /// Older entries without staked_at are still accepted
+    pub fn parse(account_info: &str) -> StdResult<GenericAccount> {
+        let mut account: Vec<AccountInfo> = vec![];
+        for entry in account_info.split(';') {
+            let infos: Vec<&str> = entry.split(':').collect();
+            if infos.len() != 4 && infos.len() != 5 {
+                continue;
+            }
+            account.push(AccountInfo {
+                addr: Addr::unchecked(infos[0]),
+                amount: infos[1].parse()?,
+                start_time: infos[2].parse().map_err(|e| StdError::parse_err("u64", e))?,
+                end_time: infos[3].parse().map_err(|e| StdError::parse_err("u64", e))?,
+                staked_at: match infos.get(4) {
+                    Some(staked_at) => staked_at.parse().map_err(|e| StdError::parse_err("u64", e))?,
+                    None => 0,
+                },
+            });
+        }
+        Ok(GenericAccount { account })
+    }
+
+    /// Writes the list back in the Escrow.account_info format
+    pub fn to_account_info(&self) -> String {
+        self.account
+            .iter()
+            .map(|a| format!(";{}:{}:{}:{}:{}", a.addr, a.amount, a.start_time, a.end_time, a.staked_at))
+            .collect()
+    }
+
+    /// Total stake per address, in order of the first stake
+    pub fn totals(&self) -> Vec<(Addr, Uint128)> {
+        let mut totals: Vec<(Addr, Uint128)> = vec![];
+        for info in self.account.iter() {
+            match totals.iter_mut().find(|(addr, _)| *addr == info.addr) {
+                Some((_, amount)) => *amount += info.amount,
+                None => totals.push((info.addr.clone(), info.amount)),
+            }
+        }
+        totals
+    }
+
+    /// Moves amount of from's stake to to, taking the oldest entries first.
+    /// Moved entries keep their staking period and stake time
+    pub fn transfer(&mut self, from: &Addr, to: &Addr, amount: Uint128) -> Result<(), ContractError> {
+        let owned: Uint128 = self.account.iter().filter(|a| a.addr == *from).map(|a| a.amount).sum();
+        if owned.is_zero() {
+            return Err(ContractError::DidntStaked {});
+        }
+        if owned < amount {
+            return Err(ContractError::InsufficientBalance {});
+        }
+
+        let mut left = amount;
+        let mut moved: Vec<AccountInfo> = vec![];
+        for info in self.account.iter_mut().filter(|a| a.addr == *from) {
+            if left.is_zero() {
+                break;
+            }
+            let part = left.min(info.amount);
+            info.amount -= part;
+            left -= part;
+            moved.push(AccountInfo {
+                addr: to.clone(),
+                amount: part,
+                start_time: info.start_time,
+                end_time: info.end_time,
+                staked_at: info.staked_at,
+            });
+        }
+        self.account.retain(|a| !a.amount.is_zero());
+        self.account.extend(moved);
+        Ok(())
+    }
+
     pub fn add_account(&mut self, add: AccountInfo) {
         let index = self.account.iter().enumerate().find_map(|(i, exist)| {
             if exist.addr == add.addr {
@@ -42,72 +125,100 @@ impl GenericAccount {
 
 
 impl GenericBalance {
-    pub fn add_tokens(&mut self, add: Balance) {
+    /// Adds the tokens, failing instead of overflowing
+    pub fn try_add(&mut self, add: Balance) -> Result<(), ContractError> {
         match add {
             Balance::Native(balance) => {
                 for token in balance.0 {
-                    let index = self.native.iter().enumerate().find_map(|(i, exist)| {
-                        if exist.denom == token.denom {
-                            Some(i)
-                        } else {
-                            None
+                    match self.native.iter_mut().find(|exist| exist.denom == token.denom) {
+                        Some(exist) => {
+                            exist.amount = exist.amount.checked_add(token.amount).map_err(StdError::from)?
                         }
-                    });
-                    match index {
-                        Some(idx) => self.native[idx].amount += token.amount,
                         None => self.native.push(token),
                     }
                 }
             }
             Balance::Cw20(token) => {
-                let index = self.cw20.iter().enumerate().find_map(|(i, exist)| {
-                    if exist.address == token.address {
-                        Some(i)
-                    } else {
-                        None
+                match self.cw20.iter_mut().find(|exist| exist.address == token.address) {
+                    Some(exist) => {
+                        exist.amount = exist.amount.checked_add(token.amount).map_err(StdError::from)?
                     }
-                });
-                match index {
-                    Some(idx) => self.cw20[idx].amount += token.amount,
                     None => self.cw20.push(token),
                 }
             }
         };
+        self.prune();
+        Ok(())
     }
 
-    pub fn sub_tokens(&mut self, sub: Balance) {
+    /// Removes the tokens, failing if any of them is missing or short
+    pub fn try_sub(&mut self, sub: Balance) -> Result<(), ContractError> {
         match sub {
             Balance::Native(balance) => {
                 for token in balance.0 {
-                    let index = self.native.iter().enumerate().find_map(|(i, exist)| {
-                        if exist.denom == token.denom {
-                            Some(i)
-                        } else {
-                            None
-                        }
-                    });
-                    match index {
-                        Some(idx) => self.native[idx].amount -= token.amount,
-                        None => self.native.push(token),
+                    if token.amount.is_zero() {
+                        continue;
                     }
+                    let exist = self
+                        .native
+                        .iter_mut()
+                        .find(|exist| exist.denom == token.denom)
+                        .ok_or(ContractError::InsufficientBalance {})?;
+                    exist.amount = exist
+                        .amount
+                        .checked_sub(token.amount)
+                        .map_err(|_| ContractError::InsufficientBalance {})?;
                 }
             }
             Balance::Cw20(token) => {
-                let index = self.cw20.iter().enumerate().find_map(|(i, exist)| {
-                    if exist.address == token.address {
-                        Some(i)
-                    } else {
-                        None
-                    }
-                });
-                match index {
-                    Some(idx) => self.cw20[idx].amount -= token.amount,
-                    None => self.cw20.push(token),
+                if !token.amount.is_zero() {
+                    let exist = self
+                        .cw20
+                        .iter_mut()
+                        .find(|exist| exist.address == token.address)
+                        .ok_or(ContractError::InsufficientBalance {})?;
+                    exist.amount = exist
+                        .amount
+                        .checked_sub(token.amount)
+                        .map_err(|_| ContractError::InsufficientBalance {})?;
                 }
             }
         };
+        self.prune();
+        Ok(())
+    }
+
+    /// Adds every token of another balance
+    pub fn try_add_balance(&mut self, add: &GenericBalance) -> Result<(), ContractError> {
+        if !add.native.is_empty() {
+            self.try_add(Balance::from(add.native.clone()))?;
+        }
+        for token in add.cw20.iter() {
+            self.try_add(Balance::Cw20(token.clone()))?;
+        }
+        Ok(())
+    }
+
+    /// Removes every token of another balance
+    pub fn try_sub_balance(&mut self, sub: &GenericBalance) -> Result<(), ContractError> {
+        if !sub.native.is_empty() {
+            self.try_sub(Balance::from(sub.native.clone()))?;
+        }
+        for token in sub.cw20.iter() {
+            self.try_sub(Balance::Cw20(token.clone()))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.native.is_empty() && self.cw20.is_empty()
     }
 
+    /// Drops entries that went down to zero
+    fn prune(&mut self) {
+        self.native.retain(|c| !c.amount.is_zero());
+        self.cw20.retain(|c| !c.amount.is_zero());
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -119,14 +230,54 @@ pub struct Escrow {
     pub account_info: String,
     pub work_title: String,
     pub work_desc: String,
+    /// Empty until the client reveals it after funding
     pub work_url: String,
+    /// Hex sha256 of work_url committed at create
+    pub work_url_hash: String,
     pub start_time: Option<u64>,
-    pub account_min_stake_amount: u64,
-    pub stake_amount: u64,
-    /// Balance in Native and Cw20 tokens
+    pub account_min_stake_amount: Uint128,
+    pub stake_amount: Uint128,
+    /// Staked Native and Cw20 tokens, the only funds counting toward stake_amount
     pub balance: GenericBalance,
-    /// All possible contracts that we accept tokens from
-    pub cw20_whitelist: Vec<Addr>,
+    /// Rewards for the stakers, paid out pro-rata by amount and lock
+    /// duration when the client approves
+    pub reward_pool: GenericBalance,
+    /// Deposit sent with create. Returned to the client on completion,
+    /// slashed to the stakers if the client fails to deliver
+    pub client_bond: GenericBalance,
+    /// May slash the client bond besides the manager
+    pub arbiter: Option<Addr>,
+    /// How the stake is paid out on approval, fixed at create
+    pub split: RevenueSplit,
+    /// Co-creators sharing the client share by weight.
+    /// Empty means the client gets all of it
+    pub collaborators: Vec<Collaborator>,
+    /// The registered cw20 this work is staked in, chosen at create
+    pub stake_token: Addr,
+    /// Read from the token's TokenInfo at create, used for display only
+    pub stake_decimals: u8,
+    pub stake_symbol: String,
+    /// Seconds a refund stays locked, overrides the contract wide period
+    pub unbonding_period: Option<u64>,
+    /// Only members of this cw4 group can top up
+    pub group: Option<GroupGate>,
+    /// Most any address can have staked, unlimited when None
+    pub max_stake_per_address: Option<Uint128>,
+    /// Most distinct stakers, unlimited when None
+    pub max_stakers: Option<u32>,
+    /// Prices each new staker slot, only the token's min_stake applies when None
+    pub pricing: Option<BondingCurve>,
+    /// Restricts who can top up. None leaves the work open to everyone
+    pub allowlist: Option<Allowlist>,
+    /// Block time in seconds the work reached stake_amount, None while unfunded
+    pub funded_at: Option<u64>,
+    /// Submitted by the client once funded, required before the client approves
+    pub deliverable: Option<Deliverable>,
+    /// Stakers who flagged the current deliverable during its review window
+    pub flagged_by: Vec<Addr>,
+    /// Set once the bond is slashed or withdrawn. A closed work takes no more
+    /// stake, the stakers can refund and the client can no longer submit or approve
+    pub closed: bool,
     pub state: u8,
     pub image_url: String
 }
@@ -142,16 +293,480 @@ impl Escrow {
         false
     }
 
-    pub fn human_whitelist(&self) -> Vec<String> {
-        self.cw20_whitelist.iter().map(|a| a.to_string()).collect()
+    /// Amount of the stake token currently held for this work
+    pub fn staked(&self) -> Uint128 {
+        self.balance
+            .cw20
+            .iter()
+            .find(|c| c.address == self.stake_token)
+            .map(|c| c.amount)
+            .unwrap_or_default()
+    }
+
+    /// Once somebody staked, the work they paid for must not change under them
+    pub fn is_funded(&self) -> bool {
+        self.state > 0 || !self.account_info.is_empty()
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    /// The work url is only edited as its hash (work_url_hash), so the url itself never shows here
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct WorkEdit {
+    pub editor: Addr,
+    pub time: u64,
+    pub funded: bool,
+    pub changes: Vec<FieldChange>,
+}
+
 pub const ESCROWS: Map<&str, Escrow> = Map::new("escrow");
 pub const CONSTANT: Map<&str, String> = Map::new("constant");
+
+/// Emergency switches, each one blocks a single deposit path.
+/// Refunds and approvals are never paused so funds can always leave.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct PauseState {
+    pub create: bool,
+    pub top_up: bool,
+    pub receive: bool,
+}
+
+pub const PAUSE: Item<PauseState> = Item::new("pause");
+/// A cw20 the admin accepts as stake
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakeToken {
+    /// Smallest single top up accepted in this token
+    pub min_stake: Uint128,
+    /// Disabled tokens can not be used for new works or top ups
+    pub enabled: bool,
+}
+
+pub const STAKE_TOKENS: Map<&Addr, StakeToken> = Map::new("stake_tokens");
+/// Payouts waiting for their owner to pull them with Claim {}
+pub const CLAIMABLE: Map<&Addr, GenericBalance> = Map::new("claimable");
+
+/// A pushed transfer, kept until its reply comes back
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingTransfer {
+    pub recipient: Addr,
+    pub balance: GenericBalance,
+}
+
+/// Keyed by the reply id of the transfer submessage
+pub const PENDING_TRANSFERS: Map<u64, PendingTransfer> = Map::new("pending_transfers");
+pub const TRANSFER_SEQ: Item<u64> = Item::new("transfer_seq");
+
+/// A refund that becomes claimable once release_at is reached
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UnbondingClaim {
+    pub id: String,
+    pub balance: GenericBalance,
+    pub release_at: Expiration,
+}
+
+pub const UNBONDING: Map<&Addr, Vec<UnbondingClaim>> = Map::new("unbonding");
+
+/// Charged on every create, on top of the deposit
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingFee {
+    pub amount: Uint128,
+    pub denom: Denom,
+    /// Receives the fee as soon as the work is created
+    pub collector: Addr,
+}
+
+impl ListingFee {
+    pub fn balance(&self) -> GenericBalance {
+        denom_balance(&self.denom, self.amount)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CommissionStatus {
+    /// Waiting for the artist to submit
+    Open,
+    Submitted,
+    /// Paid out to the artist
+    Paid,
+    /// Returned to the client
+    Refunded,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Deliverable {
+    pub url: String,
+    pub content_hash: String,
+    pub submitted_at: u64,
+    /// End of the review, fixed at submit so later config changes don't move it
+    pub review_deadline: u64,
+}
+
+/// Work a client pays a named artist for, outside the staking flow
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Commission {
+    pub client: Addr,
+    pub artist: Addr,
+    /// Can approve or reject in place of the client
+    pub arbiter: Option<Addr>,
+    pub work_title: String,
+    pub work_desc: String,
+    pub balance: GenericBalance,
+    /// Block time in seconds after which an open commission can be refunded
+    pub submit_deadline: u64,
+    /// Seconds after the submission the artist can take the payout unreviewed
+    pub review_period: u64,
+    pub deliverable: Option<Deliverable>,
+    pub status: CommissionStatus,
+}
+
+impl Commission {
+    pub fn can_review(&self, sender: &Addr) -> bool {
+        self.client == *sender || self.arbiter.as_ref() == Some(sender)
+    }
+}
+
+pub const COMMISSIONS: Map<&str, Commission> = Map::new("commissions");
+
+/// Position offered on the market at a fixed price
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Listing {
+    /// Escrow id of the position
+    pub id: String,
+    pub seller: Addr,
+    pub amount: Uint128,
+    pub price: Uint128,
+    pub denom: Denom,
+}
+
+pub const LISTINGS: Map<u64, Listing> = Map::new("listings");
+pub const LISTING_SEQ: Item<u64> = Item::new("listing_seq");
+/// Open listing ids per (escrow id, seller), their stake is held back for the buyers
+pub const SELLER_LISTINGS: Map<(&str, &Addr), Vec<u64>> = Map::new("seller_listings");
+/// Native denoms positions can be sold for, cw20 prices must be registered stake tokens
+pub const MARKET_DENOMS: Map<&str, bool> = Map::new("market_denoms");
+
+pub const LISTING_FEE: Item<ListingFee> = Item::new("listing_fee");
+/// Addresses that create works without paying the listing fee
+pub const FEE_EXEMPT: Map<&Addr, bool> = Map::new("fee_exempt");
+/// Listing fees paid ahead with PayListingFee, one per create
+pub const FEE_CREDITS: Map<&Addr, u32> = Map::new("fee_credits");
+
+/// Basis points of a whole payout, 10000 is 100%
+pub const TOTAL_BPS: u16 = 10000;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SplitShare {
+    pub address: Addr,
+    pub bps: u16,
+}
+
+/// Shares of the stake in basis points, summing to TOTAL_BPS
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RevenueSplit {
+    pub client_bps: u16,
+    /// Paid to the manager (treasury) together with any rounding dust
+    pub manager_bps: u16,
+    /// Returned to the stakers pro-rata as their reward
+    pub stakers_bps: u16,
+    /// Fixed extra recipients such as collaborators
+    pub extra_shares: Vec<SplitShare>,
+}
+
+impl RevenueSplit {
+    pub fn total_bps(&self) -> u32 {
+        self.extra_shares
+            .iter()
+            .fold(self.client_bps as u32 + self.manager_bps as u32 + self.stakers_bps as u32, |sum, share| {
+                sum + share.bps as u32
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Collaborator {
+    /// Address named at create, the only one allowed to change the payout
+    pub member: Addr,
+    /// Where this collaborator's part of the client share goes
+    pub payout: Addr,
+    pub weight: u64,
+}
+
+/// Bounds every per-work split must stay in
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SplitLimits {
+    pub min_manager_bps: u16,
+    pub max_client_bps: u16,
+    pub max_stakers_bps: u16,
+}
+
+pub const DEFAULT_SPLIT: Item<RevenueSplit> = Item::new("default_split");
+pub const SPLIT_LIMITS: Item<SplitLimits> = Item::new("split_limits");
+
+/// Workshop wide reward stream, shared per second by every active stake
+/// (a work not yet approved by its client) weighted by amount
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Emission {
+    pub reward_token: Addr,
+    /// Only stakes in this token earn, so amounts of tokens with
+    /// different decimals are never added up
+    pub stake_token: Addr,
+    /// Reward tokens per second until period_finish
+    pub rate: Uint128,
+    pub period_finish: u64,
+    pub last_update: u64,
+    pub reward_per_token: Decimal,
+    pub total_staked: Uint128,
+}
+
+/// Decimal places of Decimal atomics
+const DECIMAL_PLACES: u32 = 18;
+
+impl Emission {
+    /// Brings reward_per_token up to `now`. While nothing is staked the stream is
+    /// held, so the period finishes that much later instead of emitting to nobody
+    pub fn update(&mut self, now: u64) -> StdResult<()> {
+        if self.total_staked.is_zero() {
+            if self.last_update < self.period_finish && now > self.last_update {
+                let remaining = self.period_finish - self.last_update;
+                self.period_finish = now.checked_add(remaining).ok_or_else(|| {
+                    StdError::generic_err("Emission period overflows")
+                })?;
+            }
+            self.last_update = self.last_update.max(now);
+            return Ok(());
+        }
+        let applicable = now.min(self.period_finish).max(self.last_update);
+        let emitted = self.rate.checked_mul(Uint128::from(applicable - self.last_update))?;
+        let added = Uint128::try_from(
+            Uint256::from(emitted).multiply_ratio(10u128.pow(DECIMAL_PLACES), self.total_staked),
+        )?;
+        let atomics = self.reward_per_token.atomics().checked_add(added)?;
+        self.reward_per_token = Decimal::from_atomics(atomics, DECIMAL_PLACES)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+        self.last_update = applicable;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct StakerEmission {
+    pub staked: Uint128,
+    pub reward_per_token_paid: Decimal,
+    /// Earned but not yet moved to the claimable balance
+    pub pending: Uint128,
+}
+
+impl StakerEmission {
+    /// Credits what was earned since the last change of stake
+    pub fn settle(&mut self, reward_per_token: Decimal) -> StdResult<()> {
+        let delta = reward_per_token.atomics().checked_sub(self.reward_per_token_paid.atomics())?;
+        let earned = Uint128::try_from(
+            Uint256::from(self.staked).multiply_ratio(delta, 10u128.pow(DECIMAL_PLACES)),
+        )?;
+        self.pending = self.pending.checked_add(earned)?;
+        self.reward_per_token_paid = reward_per_token;
+        Ok(())
+    }
+}
+
+pub const EMISSION: Item<Emission> = Item::new("emission");
+pub const STAKER_EMISSIONS: Map<&Addr, StakerEmission> = Map::new("staker_emissions");
+
+/// cw721 contract the access passes are minted on, this contract must be its minter
+pub const ACCESS_PASS_NFT: Item<Addr> = Item::new("access_pass_nft");
+/// Token id of the pass minted per (escrow id, staker)
+pub const ACCESS_PASSES: Map<(&str, &Addr), String> = Map::new("access_passes");
+/// Last pass number, so a reused escrow id never mints an existing token id
+pub const ACCESS_PASS_SEQ: Item<u64> = Item::new("access_pass_seq");
+
+/// Extension sent with every access pass mint
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AccessPassMetadata {
+    pub work_id: String,
+    /// Stake of the owner when the pass was minted
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct GroupGate {
+    pub address: Addr,
+    /// Caps a member's total stake at weight times this amount
+    pub stake_per_weight: Option<Uint128>,
+}
+
+/// cw4 group whose admin may act as manager besides manager_addr
+pub const MANAGER_GROUP: Item<Addr> = Item::new("manager_group");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Allowlist {
+    /// Addresses kept in ALLOWLISTS by the client
+    Addresses,
+    /// Hex sha256 root over the sha256 of each address, pairs hashed in sorted order
+    MerkleRoot(String),
+}
+
+/// Addresses allowed to top up a work with an Allowlist::Addresses list
+pub const ALLOWLISTS: Map<(&str, &Addr), bool> = Map::new("allowlists");
+
+/// secp256k1 public key a staker registered for a work, compressed or not
+pub const STAKER_PUBKEYS: Map<(&str, &Addr), Binary> = Map::new("staker_pubkeys");
+/// Work url encrypted by the client to the staker's public key
+pub const ENCRYPTED_URLS: Map<(&str, &Addr), Binary> = Map::new("encrypted_urls");
+
+/// Minimum stake of the next staker slot, given the slots already taken
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BondingCurve {
+    /// base + slope * slots
+    Linear { base: Uint128, slope: Uint128 },
+    /// base * (1 + growth_bps / 10000) ^ slots, rounded down
+    Exponential { base: Uint128, growth_bps: u16 },
+}
+
+impl BondingCurve {
+    pub fn price(&self, slots: u32) -> StdResult<Uint128> {
+        match self {
+            BondingCurve::Linear { base, slope } => {
+                let rise = slope.checked_mul(Uint128::from(slots))?;
+                Ok(base.checked_add(rise)?)
+            }
+            BondingCurve::Exponential { base, growth_bps } => {
+                // compounds with DECIMAL_PLACES of precision and rounds down once at the end,
+                // rounding every step would keep small prices from ever growing
+                let one = Uint256::from(10u128.pow(DECIMAL_PLACES));
+                let factor = one.multiply_ratio(TOTAL_BPS as u128 + *growth_bps as u128, TOTAL_BPS as u128);
+                let mut price = Uint256::from(*base).checked_mul(one)?;
+                for _ in 0..slots {
+                    price = price.checked_mul(factor)? / one;
+                }
+                Ok(Uint128::try_from(price / one)?)
+            }
+        }
+    }
+}
+
+/// Deposit queued while the work has no free staker slot
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct WaitlistEntry {
+    pub addr: Addr,
+    pub amount: Uint128,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+/// Waiting deposits per escrow id, first come first promoted
+pub const WAITLISTS: Map<&str, Vec<WaitlistEntry>> = Map::new("waitlists");
+
+/// Metadata edits per escrow id, oldest first
+pub const WORK_HISTORY: Map<&str, Vec<WorkEdit>> = Map::new("work_history");
+
+/// Amount of a single denom as a balance
+pub fn denom_balance(denom: &Denom, amount: Uint128) -> GenericBalance {
+    match denom {
+        Denom::Native(denom) => GenericBalance {
+            native: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+            cw20: vec![],
+        },
+        Denom::Cw20(address) => GenericBalance {
+            native: vec![],
+            cw20: vec![Cw20CoinVerified {
+                address: address.clone(),
+                amount,
+            }],
+        },
+    }
+}
+
 /// This returns the list of ids for all registered escrows
 pub fn all_escrow_ids(storage: &dyn Storage) -> StdResult<Vec<String>> {
     ESCROWS
         .keys(storage, None, None, Order::Ascending)
         .collect()
 }
+
+/// Credits a payout to the claimable balance of an address
+pub fn add_claimable(storage: &mut dyn Storage, addr: &Addr, balance: &GenericBalance) -> Result<(), ContractError> {
+    let mut claimable = CLAIMABLE.may_load(storage, addr)?.unwrap_or_default();
+    claimable.try_add_balance(balance)?;
+    CLAIMABLE.save(storage, addr, &claimable)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coins;
+
+    fn balance() -> GenericBalance {
+        GenericBalance {
+            native: coins(100, "ujuno"),
+            cw20: vec![Cw20CoinVerified { address: Addr::unchecked("crew"), amount: Uint128::new(50) }],
+        }
+    }
+
+    #[test]
+    fn exponential_price_compounds_before_rounding() {
+        let curve = BondingCurve::Exponential { base: Uint128::new(10), growth_bps: 500 };
+        let prices: Vec<u128> = [0, 1, 2, 10, 15].iter().map(|slots| curve.price(*slots).unwrap().u128()).collect();
+        assert_eq!(prices, vec![10, 10, 11, 16, 20]);
+
+        let flat = BondingCurve::Exponential { base: Uint128::new(1_000), growth_bps: 0 };
+        assert_eq!(flat.price(50).unwrap().u128(), 1_000);
+    }
+
+    #[test]
+    fn try_sub_fails_on_underflow() {
+        let mut native = balance();
+        let err = native.try_sub(Balance::from(coins(101, "ujuno"))).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+
+        let mut cw20 = balance();
+        let err = cw20
+            .try_sub(Balance::Cw20(Cw20CoinVerified { address: Addr::unchecked("crew"), amount: Uint128::new(51) }))
+            .unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+    }
+
+    #[test]
+    fn try_sub_fails_on_missing_token() {
+        let mut native = balance();
+        let err = native.try_sub(Balance::from(coins(1, "uatom"))).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+
+        let mut cw20 = balance();
+        let err = cw20
+            .try_sub(Balance::Cw20(Cw20CoinVerified { address: Addr::unchecked("other"), amount: Uint128::new(1) }))
+            .unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+
+        // nothing to take of a token that isn't there is fine
+        let mut zero = balance();
+        zero.try_sub(Balance::from(coins(0, "uatom"))).unwrap();
+        assert_eq!(zero, balance());
+    }
+
+    #[test]
+    fn try_sub_prunes_emptied_entries() {
+        let mut balance = balance();
+        balance.try_sub(Balance::from(coins(40, "ujuno"))).unwrap();
+        assert_eq!(balance.native, coins(60, "ujuno"));
+
+        balance.try_sub(Balance::from(coins(60, "ujuno"))).unwrap();
+        assert!(balance.native.is_empty());
+        balance
+            .try_sub(Balance::Cw20(Cw20CoinVerified { address: Addr::unchecked("crew"), amount: Uint128::new(50) }))
+            .unwrap();
+        assert!(balance.is_empty());
+    }
+}